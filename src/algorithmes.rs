@@ -0,0 +1,809 @@
+use std::str::FromStr;
+
+use image::{GrayImage, Luma, Rgb, RgbImage};
+use rand::Rng;
+
+use crate::diffusion_points::{self, ClassesDiffusionPoints};
+use crate::diffusion_variable::{self, CoefficientsVariables};
+use crate::erreur::DitherError;
+use crate::melange::{self, MelangePalette};
+use crate::palette::Palette;
+use crate::recherche_directe::{self, EtatInitialDbs};
+use crate::espaces_couleur;
+use crate::trame::{FonctionPoint, Trame};
+use crate::utils;
+use crate::Ditherer;
+
+/// Récupérer la paire de couleurs utilisée par les algorithmes binaires : la plus claire des deux
+/// premières couleurs de la palette pour les pixels clairs, l'autre pour les pixels sombres, quel
+/// que soit leur ordre dans la palette
+fn couleurs_binaires(palette: &Palette) -> Result<(Rgb<u8>, Rgb<u8>), DitherError> {
+    match palette.couleurs() {
+        [couleur_1, couleur_2, ..] if utils::luminosite_pixel(couleur_1) < utils::luminosite_pixel(couleur_2) => {
+            Ok((*couleur_2, *couleur_1))
+        }
+        [couleur_1, couleur_2, ..] => Ok((*couleur_1, *couleur_2)),
+        couleurs => Err(DitherError::PaletteInvalide(format!(
+            "2 couleurs sont nécessaires, la palette n'en contient que {}",
+            couleurs.len()
+        ))),
+    }
+}
+
+/// Choix du seuil de luminosité du seuillage monochrome
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChoixSeuil {
+    /// Seuil fixe entre 0 et 255
+    Valeur(f32),
+    /// Seuil d'Otsu, calculé sur l'histogramme des luminosités
+    Otsu,
+    /// Luminosité moyenne de l'image
+    Moyenne,
+    /// Luminosité médiane de l'image : autant de pixels de chaque couleur
+    Mediane,
+}
+
+impl Default for ChoixSeuil {
+    fn default() -> Self {
+        // Seuillage à 50%
+        ChoixSeuil::Valeur(128.0)
+    }
+}
+
+// Implémentation de FromStr pour Enum
+impl FromStr for ChoixSeuil {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" | "otsu" => Ok(ChoixSeuil::Otsu),
+            "moyenne" => Ok(ChoixSeuil::Moyenne),
+            "mediane" => Ok(ChoixSeuil::Mediane),
+            valeur => match valeur.parse::<f32>() {
+                Ok(seuil) if (0.0..=255.0).contains(&seuil) => Ok(ChoixSeuil::Valeur(seuil)),
+                _ => Err(format!("Seuil invalide: {}", s)),
+            },
+        }
+    }
+}
+
+impl ChoixSeuil {
+    /// Calculer le seuil à appliquer à une image
+    pub fn calculer(&self, img: &RgbImage) -> f32 {
+        match self {
+            ChoixSeuil::Valeur(seuil) => *seuil,
+            ChoixSeuil::Otsu => utils::seuil_otsu(&utils::histogramme_luminosite(img)),
+            ChoixSeuil::Moyenne => utils::luminosite_moyenne(img),
+            ChoixSeuil::Mediane => utils::seuil_median(&utils::histogramme_luminosite(img)),
+        }
+    }
+}
+
+/// Seuillage monochrome sur la luminosité : les pixels plus clairs que le seuil prennent la
+/// première couleur de la palette, les autres la seconde
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Seuillage {
+    /// Choix du seuil, calculé pour chaque image
+    pub seuil: ChoixSeuil,
+}
+
+impl Ditherer for Seuillage {
+    fn dither(&self, img: &RgbImage, palette: &Palette) -> Result<RgbImage, DitherError> {
+        let mut image_rgb8 = img.clone();
+        let (couleur_1, couleur_2) = couleurs_binaires(palette)?;
+        utils::monochrome_par_seuillage(&mut image_rgb8, self.seuil.calculer(img), couleur_1, couleur_2);
+        Ok(image_rgb8)
+    }
+}
+
+/// Remplacement de chaque pixel par la couleur la plus proche de la palette, sans tramage
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlusProcheCouleur;
+
+impl Ditherer for PlusProcheCouleur {
+    fn dither(&self, img: &RgbImage, palette: &Palette) -> Result<RgbImage, DitherError> {
+        let mut image_rgb8 = img.clone();
+        utils::monochrome_par_palette(&mut image_rgb8, palette.couleurs(), palette.metrique());
+        Ok(image_rgb8)
+    }
+}
+
+/// Tramage aléatoire : chaque pixel est comparé à un seuil tiré au hasard
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TramageAleatoire {
+    /// Mélange des couleurs d'une palette quelconque, ou `None` pour un tramage binaire
+    pub melange: Option<MelangePalette>,
+    /// Écart de seuil du mélange par décalage, calculé d'après la palette si absent
+    pub ecart: Option<f32>,
+}
+
+impl TramageAleatoire {
+    /// Tramer vers toutes les couleurs de la palette avec le mélange donné
+    pub fn avec_melange(mut self, melange: MelangePalette) -> Self {
+        self.melange = Some(melange);
+        self
+    }
+
+    /// Fixer l'écart de seuil du mélange par décalage
+    pub fn avec_ecart(mut self, ecart: f32) -> Self {
+        self.ecart = Some(ecart);
+        self
+    }
+}
+
+impl Ditherer for TramageAleatoire {
+    fn dither(&self, img: &RgbImage, palette: &Palette) -> Result<RgbImage, DitherError> {
+        if let Some(melange) = self.melange {
+            let ecart = self.ecart.unwrap_or_else(|| melange::ecart_par_defaut(palette));
+            let mut rng = rand::thread_rng();
+            return melange.tramer(img, palette, ecart, |_, _| rng.gen());
+        }
+        let mut image_rgb8 = img.clone();
+        let (couleur_1, couleur_2) = couleurs_binaires(palette)?;
+        utils::tramage_aleatoire(&mut image_rgb8, couleur_1, couleur_2);
+        Ok(image_rgb8)
+    }
+}
+
+/// Ordre maximal des matrices de Bayer (64x64) : la taille de la matrice croît en 4^ordre
+pub const ORDRE_BAYER_MAX: u32 = 6;
+
+/// Tramage ordonné à partir d'une matrice de seuils (par exemple une matrice de Bayer)
+#[derive(Debug, Clone, PartialEq)]
+pub struct TramageOrdonne {
+    pub matrice: Vec<Vec<f32>>,
+    /// Nombre de niveaux de seuil : les valeurs de la matrice divisées par ce nombre sont entre 0 et 1
+    pub niveaux: f32,
+    /// Mélange des couleurs d'une palette quelconque, ou `None` pour un tramage binaire
+    pub melange: Option<MelangePalette>,
+    /// Écart de seuil du mélange par décalage, calculé d'après la palette si absent
+    pub ecart: Option<f32>,
+}
+
+impl TramageOrdonne {
+    /// Créer un tramage ordonné utilisant la matrice de Bayer d'ordre donné (taille 2^ordre),
+    /// l'ordre étant limité à [`ORDRE_BAYER_MAX`] car la mémoire croît avec le carré de la taille
+    pub fn bayer(ordre: u32) -> Result<Self, DitherError> {
+        if ordre > ORDRE_BAYER_MAX {
+            return Err(DitherError::MatriceInvalide(format!(
+                "l'ordre de la matrice de Bayer doit être compris entre 0 et {} ({})",
+                ORDRE_BAYER_MAX, ordre
+            )));
+        }
+        TramageOrdonne::depuis_matrice(utils::generer_matrice_bayer(ordre))
+    }
+
+    /// Créer un tramage ordonné à partir d'une matrice contenant les rangs de ses cases
+    /// (Bayer, bruit bleu...), non vide et dont toutes les lignes ont la même longueur
+    pub fn depuis_matrice(matrice: Vec<Vec<f32>>) -> Result<Self, DitherError> {
+        verifier_matrice(&matrice)?;
+        let niveaux = (matrice.len() * matrice[0].len()) as f32;
+        Ok(TramageOrdonne { matrice, niveaux, melange: None, ecart: None })
+    }
+
+    /// Créer un tramage ordonné à partir d'une carte de seuils en niveaux de gris (valeurs entre 0 et 255),
+    /// voir [`utils::charger_carte_seuil`]
+    pub fn depuis_carte_seuil(carte: Vec<Vec<f32>>) -> Result<Self, DitherError> {
+        verifier_matrice(&carte)?;
+        Ok(TramageOrdonne { matrice: carte, niveaux: 256.0, melange: None, ecart: None })
+    }
+
+    /// Tramer vers toutes les couleurs de la palette avec le mélange donné
+    pub fn avec_melange(mut self, melange: MelangePalette) -> Self {
+        self.melange = Some(melange);
+        self
+    }
+
+    /// Fixer l'écart de seuil du mélange par décalage
+    pub fn avec_ecart(mut self, ecart: f32) -> Self {
+        self.ecart = Some(ecart);
+        self
+    }
+}
+
+/// Vérifier qu'une matrice de seuils est non vide et rectangulaire
+fn verifier_matrice(matrice: &[Vec<f32>]) -> Result<(), DitherError> {
+    let Some(premiere) = matrice.first().filter(|ligne| !ligne.is_empty()) else {
+        return Err(DitherError::MatriceInvalide("la matrice est vide".to_string()));
+    };
+    if let Some(i) = matrice.iter().position(|ligne| ligne.len() != premiere.len()) {
+        return Err(DitherError::MatriceInvalide(format!(
+            "la ligne {} a {} valeurs au lieu de {}",
+            i + 1,
+            matrice[i].len(),
+            premiere.len()
+        )));
+    }
+    Ok(())
+}
+
+impl Ditherer for TramageOrdonne {
+    fn dither(&self, img: &RgbImage, palette: &Palette) -> Result<RgbImage, DitherError> {
+        if let Some(melange) = self.melange {
+            let ecart = self.ecart.unwrap_or_else(|| melange::ecart_par_defaut(palette));
+            let (largeur, hauteur) = (self.matrice.len(), self.matrice[0].len());
+            // Seuils centrés dans leur intervalle pour que le décalage moyen soit nul
+            return melange.tramer(img, palette, ecart, |x, y| {
+                (self.matrice[x as usize % largeur][y as usize % hauteur] + 0.5) / self.niveaux
+            });
+        }
+        let mut image_rgb8 = img.clone();
+        let (couleur_1, couleur_2) = couleurs_binaires(palette)?;
+        utils::tramage_ordonne(&mut image_rgb8, &self.matrice, self.niveaux, couleur_1, couleur_2);
+        Ok(image_rgb8)
+    }
+}
+
+/// Similigravure : trame d'impression à points groupés (AM), dont les points grossissent avec la densité
+#[derive(Debug, Clone, PartialEq)]
+pub struct Similigravure {
+    pub trame: Trame,
+}
+
+impl Ditherer for Similigravure {
+    fn dither(&self, img: &RgbImage, palette: &Palette) -> Result<RgbImage, DitherError> {
+        let mut image_rgb8 = img.clone();
+        let (couleur_1, couleur_2) = couleurs_binaires(palette)?;
+        for (x, y, pixel) in image_rgb8.enumerate_pixels_mut() {
+            let densite = 1.0 - utils::luminosite_pixel(pixel) / 255.0;
+            *pixel = if densite > self.trame.seuil(x, y) { couleur_2 } else { couleur_1 };
+        }
+        Ok(image_rgb8)
+    }
+}
+
+/// Diffusion d'erreur de Riemersma : parcours de l'image le long d'une courbe de Hilbert avec un
+/// historique des dernières erreurs dont le poids décroît exponentiellement
+#[derive(Debug, Clone, PartialEq)]
+pub struct Riemersma {
+    /// Nombre d'erreurs conservées dans l'historique
+    pub longueur_historique: usize,
+    /// Poids de l'erreur la plus ancienne, celui de la plus récente valant 1
+    pub rapport: f32,
+}
+
+impl Default for Riemersma {
+    fn default() -> Self {
+        // Valeurs proposées par Thiadmer Riemersma
+        Riemersma { longueur_historique: 16, rapport: 1.0 / 16.0 }
+    }
+}
+
+impl Ditherer for Riemersma {
+    fn dither(&self, img: &RgbImage, palette: &Palette) -> Result<RgbImage, DitherError> {
+        let mut image_rgb8 = img.clone();
+        utils::diffusion_riemersma(&mut image_rgb8, palette.couleurs(), palette.metrique(), self.longueur_historique, self.rapport);
+        Ok(image_rgb8)
+    }
+}
+
+/// Diffusion par points de Knuth : l'image est pavée par une matrice de classes, les pixels sont traités
+/// par classe croissante et l'erreur n'est diffusée qu'aux voisins de classe supérieure
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffusionPoints {
+    /// Matrice de classes, répétée sur toute l'image (indexée par `classes[y][x]`)
+    pub classes: Vec<Vec<usize>>,
+}
+
+impl DiffusionPoints {
+    /// Créer une diffusion par points avec l'une des matrices de classes disponibles
+    pub fn new(classes: ClassesDiffusionPoints) -> Self {
+        DiffusionPoints { classes: classes.matrice() }
+    }
+}
+
+impl Default for DiffusionPoints {
+    fn default() -> Self {
+        DiffusionPoints::new(ClassesDiffusionPoints::Knuth)
+    }
+}
+
+impl Ditherer for DiffusionPoints {
+    fn dither(&self, img: &RgbImage, palette: &Palette) -> Result<RgbImage, DitherError> {
+        let mut image_rgb8 = img.clone();
+        diffusion_points::diffusion_points(&mut image_rgb8, palette.couleurs(), palette.metrique(), &self.classes);
+        Ok(image_rgb8)
+    }
+}
+
+/// Diffusion d'erreur à coefficients variables (Ostromoukhov, Zhou-Fang) : la matrice de diffusion
+/// de chaque pixel dépend de sa luminosité d'origine
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiffusionVariable {
+    /// Table de coefficients utilisée
+    pub coefficients: CoefficientsVariables,
+}
+
+impl DiffusionVariable {
+    /// Créer une diffusion à coefficients variables avec la table donnée
+    pub fn new(coefficients: CoefficientsVariables) -> Self {
+        DiffusionVariable { coefficients }
+    }
+}
+
+impl Ditherer for DiffusionVariable {
+    fn dither(&self, img: &RgbImage, palette: &Palette) -> Result<RgbImage, DitherError> {
+        let mut image_rgb8 = img.clone();
+        diffusion_variable::diffusion_variable(&mut image_rgb8, palette.couleurs(), palette.metrique(), self.coefficients);
+        Ok(image_rgb8)
+    }
+}
+
+/// Nombre de passes maximal par défaut de la recherche binaire directe
+pub const PASSES_DBS_PAR_DEFAUT: usize = 10;
+
+/// Recherche binaire directe : tramage binaire itératif minimisant l'erreur perçue à partir
+/// du résultat d'un autre tramage. Beaucoup plus lent, à réserver aux images soignées
+#[derive(Debug, Clone, PartialEq)]
+pub struct RechercheBinaireDirecte {
+    /// Tramage de départ
+    pub initial: EtatInitialDbs,
+    /// Nombre maximal de passes sur l'image
+    pub passes_max: usize,
+}
+
+impl Default for RechercheBinaireDirecte {
+    fn default() -> Self {
+        RechercheBinaireDirecte { initial: EtatInitialDbs::default(), passes_max: PASSES_DBS_PAR_DEFAUT }
+    }
+}
+
+impl RechercheBinaireDirecte {
+    /// Tramage initial en noir et blanc, `true` pour les pixels clairs
+    fn etat_initial(&self, img: &RgbImage) -> Result<Vec<bool>, DitherError> {
+        let noir_et_blanc = Palette::new(vec![Rgb([255, 255, 255]), Rgb([0, 0, 0])]);
+        let tramage = match self.initial {
+            EtatInitialDbs::Aleatoire => TramageAleatoire::default().dither(img, &noir_et_blanc)?,
+            EtatInitialDbs::Bayer => TramageOrdonne::bayer(3)?.dither(img, &noir_et_blanc)?,
+            EtatInitialDbs::FloydSteinberg => DiffusionErreur::new(MatriceDiffusionErreur::FloydSteinberg)?.dither(img, &noir_et_blanc)?,
+        };
+        Ok(tramage.pixels().map(|pixel| pixel[0] > 127).collect())
+    }
+}
+
+impl Ditherer for RechercheBinaireDirecte {
+    fn dither(&self, img: &RgbImage, palette: &Palette) -> Result<RgbImage, DitherError> {
+        let (couleur_1, couleur_2) = couleurs_binaires(palette)?;
+        let cible: Vec<f32> = img.pixels().map(|pixel| utils::luminosite_pixel(pixel) / 255.0).collect();
+        let mut etat = self.etat_initial(img)?;
+        recherche_directe::recherche_binaire_directe(&cible, &mut etat, img.width() as usize, img.height() as usize, self.passes_max);
+        let mut image_rgb8 = img.clone();
+        for (pixel, clair) in image_rgb8.pixels_mut().zip(etat) {
+            *pixel = if clair { couleur_1 } else { couleur_2 };
+        }
+        Ok(image_rgb8)
+    }
+}
+
+/// Angles de trame classiques des plaques cyan, magenta, jaune et noir, en degrés
+pub const ANGLES_CMJN: [f32; 4] = [15.0, 75.0, 0.0, 45.0];
+
+/// Noms des plaques de la quadrichromie, dans l'ordre de [`ANGLES_CMJN`]
+pub const NOMS_PLAQUES_CMJN: [&str; 4] = ["cyan", "magenta", "jaune", "noir"];
+
+/// Couleur de chaque encre imprimée seule sur papier blanc
+const ENCRES_CMJN: [[u8; 3]; 4] = [[0, 255, 255], [255, 0, 255], [255, 255, 0], [0, 0, 0]];
+
+/// Similigravure en quadrichromie : l'image est séparée en plaques cyan, magenta, jaune et noir,
+/// chacune tramée à son angle classique. Le rendu superpose les encres sur papier blanc, la
+/// palette est donc ignorée
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimiligravureCmjn {
+    /// Trames des plaques cyan, magenta, jaune et noir
+    pub trames: [Trame; 4],
+    /// Part du gris remplacée par de l'encre noire (0 à 1)
+    pub gcr: f32,
+    /// Part de ce noir retirée des encres colorées (0 à 1)
+    pub ucr: f32,
+}
+
+impl SimiligravureCmjn {
+    /// Créer une similigravure en quadrichromie aux angles [`ANGLES_CMJN`]
+    pub fn new(fonction: FonctionPoint, lpi: f32, dpi: f32, gcr: f32, ucr: f32) -> Self {
+        let trames = ANGLES_CMJN.map(|angle| Trame::new(fonction, lpi, dpi, angle));
+        SimiligravureCmjn { trames, gcr, ucr }
+    }
+
+    /// Tramer chaque plaque séparément : l'encre est en noir (0), le papier en blanc (255)
+    pub fn plaques(&self, img: &RgbImage) -> [GrayImage; 4] {
+        let mut plaques: [GrayImage; 4] =
+            std::array::from_fn(|_| GrayImage::from_pixel(img.width(), img.height(), Luma([255])));
+        for (x, y, pixel) in img.enumerate_pixels() {
+            let densites = espaces_couleur::srgb_vers_cmjn(pixel, self.gcr, self.ucr);
+            for ((plaque, trame), densite) in plaques.iter_mut().zip(&self.trames).zip(densites) {
+                if densite > trame.seuil(x, y) {
+                    plaque.put_pixel(x, y, Luma([0]));
+                }
+            }
+        }
+        plaques
+    }
+}
+
+impl Ditherer for SimiligravureCmjn {
+    fn dither(&self, img: &RgbImage, _palette: &Palette) -> Result<RgbImage, DitherError> {
+        let plaques = self.plaques(img);
+        // Les encres se superposent : chaque encre absorbe une partie de la lumière
+        Ok(RgbImage::from_fn(img.width(), img.height(), |x, y| {
+            let mut couleur = [255u16; 3];
+            for (plaque, encre) in plaques.iter().zip(ENCRES_CMJN) {
+                if plaque.get_pixel(x, y)[0] == 0 {
+                    for (composante, absorption) in couleur.iter_mut().zip(encre) {
+                        *composante = *composante * absorption as u16 / 255;
+                    }
+                }
+            }
+            Rgb(couleur.map(|c| c as u8))
+        }))
+    }
+}
+
+/// Diffusion d'erreur vers les pixels voisins selon une matrice de diffusion
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffusionErreur {
+    pub matrice: Vec<Vec<f32>>,
+    /// Case (ligne, colonne) de la matrice correspondant au pixel courant
+    pub origine: (usize, usize),
+    /// Diffuser l'erreur sur les intensités linéaires plutôt que sur les valeurs sRGB
+    pub lineaire: bool,
+    /// Alterner le sens de parcours à chaque ligne
+    pub serpentin: bool,
+}
+
+impl DiffusionErreur {
+    /// Créer une diffusion d'erreur à partir d'une des matrices prédéfinies. La matrice
+    /// personnalisée n'a pas de poids propres et se construit avec [`DiffusionErreur::personnalisee`]
+    pub fn new(matrice: MatriceDiffusionErreur) -> Result<Self, DitherError> {
+        if matrice == MatriceDiffusionErreur::Personnalisee {
+            return Err(DitherError::NoyauInvalide(
+                "la matrice personnalisée se construit à partir d'un noyau (DiffusionErreur::personnalisee)".to_string(),
+            ));
+        }
+        let matrice = matrice.matrice();
+        // Les matrices prédéfinies sont centrées sur le pixel courant
+        let origine = (matrice.len() / 2, matrice[0].len() / 2);
+        Ok(DiffusionErreur { matrice, origine, lineaire: false, serpentin: false })
+    }
+
+    /// Créer une diffusion d'erreur à partir d'un noyau écrit ligne par ligne, les lignes étant
+    /// séparées par `;` ou des retours à la ligne et les poids par des espaces (ex : `"0 * 7; 3 5 1"`).
+    /// `*` marque le pixel courant, chaque poids est divisé par `diviseur`, les lignes plus courtes
+    /// sont complétées par des zéros à droite et tout ce qui suit un `#` est ignoré. Les poids placés
+    /// avant `*` dans l'ordre de parcours visent des pixels déjà traités et doivent être nuls
+    pub fn personnalisee(noyau: &str, diviseur: f32) -> Result<Self, DitherError> {
+        if !diviseur.is_finite() || diviseur <= 0.0 {
+            return Err(DitherError::NoyauInvalide(format!("le diviseur doit être strictement positif ({})", diviseur)));
+        }
+
+        let mut matrice: Vec<Vec<f32>> = Vec::new();
+        let mut origine = None;
+        for ligne in noyau.lines().flat_map(|ligne| ligne.split('#').next().unwrap_or("").split(';')) {
+            let mut poids_ligne = Vec::new();
+            for valeur in ligne.split_whitespace() {
+                if valeur == "*" {
+                    if origine.is_some() {
+                        return Err(DitherError::NoyauInvalide("le pixel courant '*' apparaît plusieurs fois".to_string()));
+                    }
+                    origine = Some((matrice.len(), poids_ligne.len()));
+                    poids_ligne.push(0.0);
+                    continue;
+                }
+                let poids: f32 = valeur
+                    .parse()
+                    .map_err(|_| DitherError::NoyauInvalide(format!("poids invalide : {}", valeur)))?;
+                if !poids.is_finite() || poids < 0.0 {
+                    return Err(DitherError::NoyauInvalide(format!("les poids doivent être positifs ou nuls ({})", valeur)));
+                }
+                poids_ligne.push(poids / diviseur);
+            }
+            if !poids_ligne.is_empty() {
+                matrice.push(poids_ligne);
+            }
+        }
+
+        let origine = origine.ok_or_else(|| DitherError::NoyauInvalide("le pixel courant '*' est absent".to_string()))?;
+        let largeur = matrice.iter().map(Vec::len).max().unwrap_or(0);
+        for ligne in &mut matrice {
+            ligne.resize(largeur, 0.0);
+        }
+        for (i, ligne) in matrice.iter().enumerate() {
+            for (j, &poids) in ligne.iter().enumerate() {
+                if (i, j) < origine && poids != 0.0 {
+                    return Err(DitherError::NoyauInvalide(format!(
+                        "le poids {} (ligne {}, colonne {}) précède le pixel courant '*' : son erreur serait perdue",
+                        poids * diviseur,
+                        i + 1,
+                        j + 1
+                    )));
+                }
+            }
+        }
+        Ok(DiffusionErreur { matrice, origine, lineaire: false, serpentin: false })
+    }
+
+    /// Somme des poids de la matrice, égale à 1 lorsque toute l'erreur est diffusée
+    pub fn somme_poids(&self) -> f32 {
+        self.matrice.iter().flatten().sum()
+    }
+
+    /// Avertissement à afficher lorsque les poids ne somment pas à 1 : l'image s'assombrit ou
+    /// s'éclaircit car une partie de l'erreur est perdue ou amplifiée
+    pub fn avertissement_somme(&self) -> Option<String> {
+        let somme = self.somme_poids();
+        ((somme - 1.0).abs() > 1e-3).then(|| format!("la somme des poids du noyau vaut {} au lieu de 1", somme))
+    }
+
+    /// Activer ou non la diffusion en lumière linéaire
+    pub fn avec_lumiere_lineaire(mut self, lineaire: bool) -> Self {
+        self.lineaire = lineaire;
+        self
+    }
+
+    /// Activer ou non le balayage serpentin (boustrophédon)
+    pub fn avec_balayage_serpentin(mut self, serpentin: bool) -> Self {
+        self.serpentin = serpentin;
+        self
+    }
+}
+
+impl Ditherer for DiffusionErreur {
+    fn dither(&self, img: &RgbImage, palette: &Palette) -> Result<RgbImage, DitherError> {
+        let mut image_rgb8 = img.clone();
+        if self.lineaire {
+            utils::diffusion_erreur_lineaire(&mut image_rgb8, palette.couleurs(), &self.matrice, self.origine, palette.metrique(), self.serpentin);
+        } else {
+            utils::diffusion_erreur_generique(&mut image_rgb8, palette.couleurs(), &self.matrice, self.origine, palette.metrique(), self.serpentin);
+        }
+        Ok(image_rgb8)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatriceDiffusionErreur {
+    Simple2D,
+    FloydSteinberg,
+    JarvisJudiceNinke,
+    Atkinson,
+    Stucki,
+    Burkes,
+    Sierra3,
+    Sierra2,
+    SierraLite,
+    StevensonArce,
+    /// Noyau fourni par l'utilisateur, voir [`DiffusionErreur::personnalisee`]
+    Personnalisee,
+}
+
+impl MatriceDiffusionErreur {
+    /// Toutes les matrices prédéfinies, dans l'ordre de `--liste-matrices` (sans la matrice personnalisée)
+    pub const TOUTES: [MatriceDiffusionErreur; 10] = [
+        MatriceDiffusionErreur::Simple2D,
+        MatriceDiffusionErreur::FloydSteinberg,
+        MatriceDiffusionErreur::JarvisJudiceNinke,
+        MatriceDiffusionErreur::Atkinson,
+        MatriceDiffusionErreur::Stucki,
+        MatriceDiffusionErreur::Burkes,
+        MatriceDiffusionErreur::Sierra3,
+        MatriceDiffusionErreur::Sierra2,
+        MatriceDiffusionErreur::SierraLite,
+        MatriceDiffusionErreur::StevensonArce,
+    ];
+
+    /// Récupérer les coefficients de la matrice de diffusion d'erreur
+    pub fn matrice(&self) -> Vec<Vec<f32>> {
+        match self {
+            MatriceDiffusionErreur::Simple2D => utils::simple_2_d(),
+            MatriceDiffusionErreur::FloydSteinberg => utils::floyd_steinberg(),
+            MatriceDiffusionErreur::JarvisJudiceNinke => utils::jarvis_judice_ninke(),
+            MatriceDiffusionErreur::Atkinson => utils::atkinson(),
+            MatriceDiffusionErreur::Stucki => utils::stucki(),
+            MatriceDiffusionErreur::Burkes => utils::burkes(),
+            MatriceDiffusionErreur::Sierra3 => utils::sierra_3(),
+            MatriceDiffusionErreur::Sierra2 => utils::sierra_2(),
+            MatriceDiffusionErreur::SierraLite => utils::sierra_lite(),
+            MatriceDiffusionErreur::StevensonArce => utils::stevenson_arce(),
+            // Les poids sont fournis à part, voir DiffusionErreur::personnalisee
+            MatriceDiffusionErreur::Personnalisee => vec![vec![0.0]],
+        }
+    }
+
+    /// Nom de la matrice tel qu'accepté par `--matrice`
+    pub fn nom(&self) -> &'static str {
+        match self {
+            MatriceDiffusionErreur::Simple2D => "simple2d",
+            MatriceDiffusionErreur::FloydSteinberg => "floydsteinberg",
+            MatriceDiffusionErreur::JarvisJudiceNinke => "jarvisjudiceninke",
+            MatriceDiffusionErreur::Atkinson => "atkinson",
+            MatriceDiffusionErreur::Stucki => "stucki",
+            MatriceDiffusionErreur::Burkes => "burkes",
+            MatriceDiffusionErreur::Sierra3 => "sierra3",
+            MatriceDiffusionErreur::Sierra2 => "sierra2",
+            MatriceDiffusionErreur::SierraLite => "sierralite",
+            MatriceDiffusionErreur::StevensonArce => "stevensonarce",
+            MatriceDiffusionErreur::Personnalisee => "custom",
+        }
+    }
+
+    /// Dénominateur commun des coefficients de la matrice
+    pub fn diviseur(&self) -> u32 {
+        match self {
+            MatriceDiffusionErreur::Simple2D => 2,
+            MatriceDiffusionErreur::FloydSteinberg => 16,
+            MatriceDiffusionErreur::JarvisJudiceNinke => 48,
+            MatriceDiffusionErreur::Atkinson => 8,
+            MatriceDiffusionErreur::Stucki => 42,
+            MatriceDiffusionErreur::Burkes => 32,
+            MatriceDiffusionErreur::Sierra3 => 32,
+            MatriceDiffusionErreur::Sierra2 => 16,
+            MatriceDiffusionErreur::SierraLite => 4,
+            MatriceDiffusionErreur::StevensonArce => 200,
+            MatriceDiffusionErreur::Personnalisee => 1,
+        }
+    }
+
+    /// Courte description de la matrice
+    pub fn description(&self) -> &'static str {
+        match self {
+            MatriceDiffusionErreur::Simple2D => "moitié à droite, moitié en dessous",
+            MatriceDiffusionErreur::FloydSteinberg => "Floyd et Steinberg (1976)",
+            MatriceDiffusionErreur::JarvisJudiceNinke => "Jarvis, Judice et Ninke (1976), sur trois lignes",
+            MatriceDiffusionErreur::Atkinson => "Atkinson (MacPaint), ne diffuse que les 3/4 de l'erreur",
+            MatriceDiffusionErreur::Stucki => "Stucki (1981), variante plus nette de Jarvis-Judice-Ninke",
+            MatriceDiffusionErreur::Burkes => "Burkes (1988), Stucki réduit à deux lignes",
+            MatriceDiffusionErreur::Sierra3 => "Sierra (1989), sur trois lignes",
+            MatriceDiffusionErreur::Sierra2 => "Sierra à deux lignes",
+            MatriceDiffusionErreur::SierraLite => "Sierra Lite, proche de Floyd-Steinberg mais plus rapide",
+            MatriceDiffusionErreur::StevensonArce => "Stevenson et Arce (1985), pour grille hexagonale",
+            MatriceDiffusionErreur::Personnalisee => "noyau personnalisé donné par --noyau ou --noyau-fichier",
+        }
+    }
+
+    /// Représentation des poids entiers de la matrice (à diviser par [`MatriceDiffusionErreur::diviseur`]),
+    /// `*` marquant le pixel courant
+    pub fn poids(&self) -> String {
+        let matrice = self.matrice();
+        let diviseur = self.diviseur() as f32;
+        let origine = (matrice.len() / 2, matrice[0].len() / 2);
+        let mut lignes = Vec::new();
+        for (i, ligne) in matrice.iter().enumerate() {
+            // Les lignes au-dessus du pixel courant sont toujours vides
+            if i < origine.0 {
+                continue;
+            }
+            let valeurs: Vec<String> = ligne
+                .iter()
+                .enumerate()
+                .map(|(j, valeur)| {
+                    if (i, j) == origine {
+                        "*".to_string()
+                    } else if *valeur == 0.0 {
+                        ".".to_string()
+                    } else {
+                        format!("{}", (valeur * diviseur).round())
+                    }
+                })
+                .map(|texte| format!("{:>3}", texte))
+                .collect();
+            lignes.push(valeurs.join(" "));
+        }
+        lignes.join("\n")
+    }
+}
+
+// Implémentation de FromStr pour Enum
+impl FromStr for MatriceDiffusionErreur {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "simple2d" => Ok(MatriceDiffusionErreur::Simple2D),
+            "floydsteinberg" => Ok(MatriceDiffusionErreur::FloydSteinberg),
+            "jarvisjudiceninke" => Ok(MatriceDiffusionErreur::JarvisJudiceNinke),
+            "atkinson" => Ok(MatriceDiffusionErreur::Atkinson),
+            "stucki" => Ok(MatriceDiffusionErreur::Stucki),
+            "burkes" => Ok(MatriceDiffusionErreur::Burkes),
+            "sierra3" | "sierra" => Ok(MatriceDiffusionErreur::Sierra3),
+            "sierra2" => Ok(MatriceDiffusionErreur::Sierra2),
+            "sierralite" => Ok(MatriceDiffusionErreur::SierraLite),
+            "stevensonarce" => Ok(MatriceDiffusionErreur::StevensonArce),
+            "custom" | "personnalisee" => Ok(MatriceDiffusionErreur::Personnalisee),
+            _ => Err(format!("Matrice de diffusion d'erreur invalide: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn algorithmes_binaires_independants_de_l_ordre_de_la_palette() {
+        let mut img = RgbImage::new(4, 1);
+        for (x, niveau) in [0, 100, 160, 255].into_iter().enumerate() {
+            img.put_pixel(x as u32, 0, Rgb([niveau; 3]));
+        }
+        let (blanc, noir) = (Rgb([255, 255, 255]), Rgb([0, 0, 0]));
+        let ditherers: Vec<Box<dyn Ditherer>> = vec![
+            Box::new(Seuillage::default()),
+            Box::new(TramageOrdonne::bayer(1).unwrap()),
+            Box::new(RechercheBinaireDirecte::default()),
+        ];
+        for ditherer in ditherers {
+            let clair_d_abord = ditherer.dither(&img, &Palette::new(vec![blanc, noir])).unwrap();
+            let sombre_d_abord = ditherer.dither(&img, &Palette::new(vec![noir, blanc])).unwrap();
+            assert_eq!(clair_d_abord, sombre_d_abord);
+            assert_eq!(*clair_d_abord.get_pixel(0, 0), noir);
+            assert_eq!(*clair_d_abord.get_pixel(3, 0), blanc);
+        }
+    }
+
+    #[test]
+    fn tramage_ordonne_refuse_les_matrices_vides_ou_irregulieres() {
+        for matrice in [vec![], vec![vec![]], vec![vec![0.0, 1.0], vec![2.0]]] {
+            assert!(matches!(TramageOrdonne::depuis_matrice(matrice.clone()), Err(DitherError::MatriceInvalide(_))));
+            assert!(matches!(TramageOrdonne::depuis_carte_seuil(matrice), Err(DitherError::MatriceInvalide(_))));
+        }
+        assert!(TramageOrdonne::depuis_matrice(vec![vec![0.0, 2.0], vec![3.0, 1.0]]).is_ok());
+    }
+
+    #[test]
+    fn ordre_de_bayer_borne() {
+        assert_eq!(TramageOrdonne::bayer(ORDRE_BAYER_MAX).unwrap().matrice.len(), 1 << ORDRE_BAYER_MAX);
+        assert!(matches!(TramageOrdonne::bayer(ORDRE_BAYER_MAX + 1), Err(DitherError::MatriceInvalide(_))));
+        assert!(matches!(TramageOrdonne::bayer(u32::MAX), Err(DitherError::MatriceInvalide(_))));
+    }
+
+    #[test]
+    fn noyau_personnalise_en_ligne() {
+        let diffusion = DiffusionErreur::personnalisee("0 * 7; 3 5 1", 16.0).unwrap();
+        assert_eq!(diffusion.origine, (0, 1));
+        assert_eq!(diffusion.matrice, vec![vec![0.0, 0.0, 7.0 / 16.0], vec![3.0 / 16.0, 5.0 / 16.0, 1.0 / 16.0]]);
+        assert_eq!(diffusion.avertissement_somme(), None);
+    }
+
+    #[test]
+    fn noyau_personnalise_depuis_un_fichier() {
+        let fichier = "# Stucki réduit\n0 0 * 8 4\n2 4 8 4 2 # ligne suivante\n\n1 2 4\n";
+        let diffusion = DiffusionErreur::personnalisee(fichier, 42.0).unwrap();
+        assert_eq!(diffusion.origine, (0, 2));
+        assert_eq!(diffusion.matrice.len(), 3);
+        // La dernière ligne est complétée par des zéros
+        assert_eq!(diffusion.matrice[2], vec![1.0 / 42.0, 2.0 / 42.0, 4.0 / 42.0, 0.0, 0.0]);
+        assert!((diffusion.somme_poids() - 39.0 / 42.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn noyau_personnalise_refuse_les_poids_negatifs() {
+        let erreur = DiffusionErreur::personnalisee("* 7; 3 -5 1", 16.0).unwrap_err();
+        assert!(matches!(erreur, DitherError::NoyauInvalide(_)));
+    }
+
+    #[test]
+    fn noyau_personnalise_mal_forme() {
+        assert!(matches!(DiffusionErreur::personnalisee("0 7; 3 5 1", 16.0), Err(DitherError::NoyauInvalide(_))));
+        assert!(matches!(DiffusionErreur::personnalisee("* * 7", 16.0), Err(DitherError::NoyauInvalide(_))));
+        assert!(matches!(DiffusionErreur::personnalisee("* sept", 16.0), Err(DitherError::NoyauInvalide(_))));
+        assert!(matches!(DiffusionErreur::personnalisee("* 7", 0.0), Err(DitherError::NoyauInvalide(_))));
+    }
+
+    #[test]
+    fn noyau_personnalise_refuse_les_poids_avant_le_pixel_courant() {
+        assert!(matches!(DiffusionErreur::personnalisee("7 * ; 0 0 0", 7.0), Err(DitherError::NoyauInvalide(_))));
+        assert!(matches!(DiffusionErreur::personnalisee("0 1 0; 0 * 7", 8.0), Err(DitherError::NoyauInvalide(_))));
+        // Des zéros avant le pixel courant restent acceptés
+        assert!(DiffusionErreur::personnalisee("0 0 0; 0 * 7", 7.0).is_ok());
+    }
+
+    #[test]
+    fn matrice_personnalisee_sans_noyau_refusee() {
+        assert!(matches!(DiffusionErreur::new(MatriceDiffusionErreur::Personnalisee), Err(DitherError::NoyauInvalide(_))));
+        assert!(DiffusionErreur::new(MatriceDiffusionErreur::FloydSteinberg).is_ok());
+    }
+
+    #[test]
+    fn avertissement_quand_la_somme_des_poids_differe_de_1() {
+        let diffusion = DiffusionErreur::personnalisee("* 7; 3 5 1", 10.0).unwrap();
+        assert!((diffusion.somme_poids() - 1.6).abs() < 1e-6);
+        assert!(diffusion.avertissement_somme().is_some());
+    }
+}
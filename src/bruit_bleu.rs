@@ -0,0 +1,225 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Taille maximale des matrices de bruit bleu : la génération coûte de l'ordre de taille^4 opérations
+pub const TAILLE_BRUIT_BLEU_MAX: usize = 256;
+
+/// Écart type du filtre gaussien mesurant l'agrégation des pixels (valeur proposée par Ulichney)
+const SIGMA: f32 = 1.5;
+
+/// En-tête des fichiers de cache
+const MAGIQUE: &[u8; 4] = b"DPBB";
+
+/// Version du générateur et du format de cache, à incrémenter dès que l'un des deux change pour que
+/// les matrices déjà en cache ne soient pas réutilisées
+const VERSION_CACHE: u32 = 2;
+
+/// Motif binaire sur un tore, avec pour chaque case la somme des contributions gaussiennes des pixels allumés
+struct Motif {
+    taille: usize,
+    pixels: Vec<bool>,
+    energie: Vec<f32>,
+    filtre: Vec<f32>,
+}
+
+impl Motif {
+    fn new(taille: usize) -> Self {
+        // Le filtre est calculé une fois pour tous les décalages, en distance torique
+        let mut filtre = vec![0.0; taille * taille];
+        for dy in 0..taille {
+            for dx in 0..taille {
+                let ey = dy.min(taille - dy) as f32;
+                let ex = dx.min(taille - dx) as f32;
+                filtre[dy * taille + dx] = (-(ex * ex + ey * ey) / (2.0 * SIGMA * SIGMA)).exp();
+            }
+        }
+        Motif { taille, pixels: vec![false; taille * taille], energie: vec![0.0; taille * taille], filtre }
+    }
+
+    /// Allumer ou éteindre un pixel en mettant à jour l'énergie de toutes les cases
+    fn basculer(&mut self, indice: usize) {
+        let signe = if self.pixels[indice] { -1.0 } else { 1.0 };
+        self.pixels[indice] = !self.pixels[indice];
+        let (y0, x0) = (indice / self.taille, indice % self.taille);
+        for y in 0..self.taille {
+            let dy = (y + self.taille - y0) % self.taille;
+            for x in 0..self.taille {
+                let dx = (x + self.taille - x0) % self.taille;
+                self.energie[y * self.taille + x] += signe * self.filtre[dy * self.taille + dx];
+            }
+        }
+    }
+
+    /// Pixel allumé le plus entouré d'autres pixels allumés
+    fn amas_le_plus_dense(&self) -> usize {
+        (0..self.pixels.len())
+            .filter(|&i| self.pixels[i])
+            .max_by(|&a, &b| self.energie[a].total_cmp(&self.energie[b]))
+            .expect("le motif contient au moins un pixel allumé")
+    }
+
+    /// Pixel éteint le plus éloigné des pixels allumés
+    fn plus_grand_vide(&self) -> usize {
+        (0..self.pixels.len())
+            .filter(|&i| !self.pixels[i])
+            .min_by(|&a, &b| self.energie[a].total_cmp(&self.energie[b]))
+            .expect("le motif contient au moins un pixel éteint")
+    }
+}
+
+/// Générer une matrice de seuils de bruit bleu par l'algorithme void-and-cluster d'Ulichney.
+/// La matrice contient les rangs 0..taille² comme une matrice de Bayer et se répète sans couture
+pub fn generer_void_and_cluster(taille: usize, graine: u64) -> Vec<Vec<f32>> {
+    let nombre = taille * taille;
+    let mut rangs = vec![0usize; nombre];
+    let mut motif = Motif::new(taille);
+
+    // Motif initial : environ 10 % de pixels allumés au hasard
+    let mut rng = StdRng::seed_from_u64(graine);
+    let initiaux = (nombre / 10).max(1);
+    let mut allumes = 0;
+    while allumes < initiaux {
+        let indice = rng.gen_range(0..nombre);
+        if !motif.pixels[indice] {
+            motif.basculer(indice);
+            allumes += 1;
+        }
+    }
+
+    // Répartition homogène : déplacer le pixel le plus agrégé vers le plus grand vide jusqu'à stabilité
+    for _ in 0..nombre {
+        let amas = motif.amas_le_plus_dense();
+        motif.basculer(amas);
+        let vide = motif.plus_grand_vide();
+        motif.basculer(vide);
+        if vide == amas {
+            break;
+        }
+    }
+    let prototype = motif.pixels.clone();
+    let energie_prototype = motif.energie.clone();
+
+    // Phase 1 : retirer les amas un à un en leur donnant les rangs décroissants
+    for rang in (0..initiaux).rev() {
+        let amas = motif.amas_le_plus_dense();
+        motif.basculer(amas);
+        rangs[amas] = rang;
+    }
+
+    // Phase 2 : repartir du prototype et combler les vides jusqu'à la moitié des pixels
+    motif.pixels = prototype;
+    motif.energie = energie_prototype;
+    for rang in initiaux..nombre / 2 {
+        let vide = motif.plus_grand_vide();
+        motif.basculer(vide);
+        rangs[vide] = rang;
+    }
+
+    // Phase 3 : les pixels éteints deviennent minoritaires, on inverse le motif pour
+    // retirer les amas de pixels éteints, qui prennent les rangs croissants
+    let mut inverse = Motif::new(taille);
+    for indice in 0..nombre {
+        if !motif.pixels[indice] {
+            inverse.basculer(indice);
+        }
+    }
+    for rang in nombre / 2..nombre {
+        let amas = inverse.amas_le_plus_dense();
+        inverse.basculer(amas);
+        rangs[amas] = rang;
+    }
+
+    rangs.chunks(taille).map(|ligne| ligne.iter().map(|&r| r as f32).collect()).collect()
+}
+
+/// Chemin du fichier de cache d'une matrice de bruit bleu
+fn chemin_cache(dossier: &Path, taille: usize, graine: u64) -> PathBuf {
+    dossier.join(format!("bruit_bleu_v{}_{}_{}.bin", VERSION_CACHE, taille, graine))
+}
+
+/// Lire une matrice depuis le cache, `None` si le fichier est absent ou invalide
+fn lire_cache(chemin: &Path, taille: usize) -> Option<Vec<Vec<f32>>> {
+    let octets = fs::read(chemin).ok()?;
+    let nombre = taille * taille;
+    if octets.len() != 12 + 4 * nombre || &octets[..4] != MAGIQUE {
+        return None;
+    }
+    if u32::from_le_bytes(octets[4..8].try_into().ok()?) != VERSION_CACHE {
+        return None;
+    }
+    if u32::from_le_bytes(octets[8..12].try_into().ok()?) as usize != taille {
+        return None;
+    }
+    let rangs: Vec<f32> = octets[12..]
+        .chunks_exact(4)
+        .map(|mot| u32::from_le_bytes([mot[0], mot[1], mot[2], mot[3]]) as f32)
+        .collect();
+    if rangs.iter().any(|&rang| rang >= nombre as f32) {
+        return None;
+    }
+    Some(rangs.chunks(taille).map(<[f32]>::to_vec).collect())
+}
+
+/// Écrire une matrice dans le cache
+fn ecrire_cache(chemin: &Path, matrice: &[Vec<f32>]) -> std::io::Result<()> {
+    if let Some(dossier) = chemin.parent() {
+        fs::create_dir_all(dossier)?;
+    }
+    let mut octets = MAGIQUE.to_vec();
+    octets.extend_from_slice(&VERSION_CACHE.to_le_bytes());
+    octets.extend_from_slice(&(matrice.len() as u32).to_le_bytes());
+    for rang in matrice.iter().flatten() {
+        octets.extend_from_slice(&(*rang as u32).to_le_bytes());
+    }
+    fs::write(chemin, octets)
+}
+
+/// Récupérer une matrice de bruit bleu depuis le dossier de cache, ou la générer et l'y enregistrer.
+/// Le cache est facultatif : une erreur d'écriture n'empêche pas le tramage, elle est renvoyée avec la
+/// matrice et l'appelant décide de la signaler ou non
+pub fn matrice_void_and_cluster(taille: usize, graine: u64, dossier_cache: &Path) -> (Vec<Vec<f32>>, Option<std::io::Error>) {
+    let chemin = chemin_cache(dossier_cache, taille, graine);
+    if let Some(matrice) = lire_cache(&chemin, taille) {
+        return (matrice, None);
+    }
+    let matrice = generer_void_and_cluster(taille, graine);
+    // Le chemin est ajouté au message, l'erreur d'origine n'en contenant pas
+    let erreur = ecrire_cache(&chemin, &matrice)
+        .err()
+        .map(|err| std::io::Error::new(err.kind(), format!("{} : {}", chemin.display(), err)));
+    (matrice, erreur)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_relu_seulement_dans_sa_version() {
+        let dossier = std::env::temp_dir().join(format!("ditherpunk_test_cache_{}", std::process::id()));
+        let (matrice, erreur) = matrice_void_and_cluster(4, 7, &dossier);
+        assert!(erreur.is_none());
+        let chemin = chemin_cache(&dossier, 4, 7);
+        assert_eq!(lire_cache(&chemin, 4), Some(matrice));
+
+        // Un fichier d'une autre version du format est ignoré
+        let mut octets = fs::read(&chemin).unwrap();
+        octets[4..8].copy_from_slice(&(VERSION_CACHE + 1).to_le_bytes());
+        fs::write(&chemin, octets).unwrap();
+        assert_eq!(lire_cache(&chemin, 4), None);
+        fs::remove_dir_all(&dossier).unwrap();
+    }
+
+    #[test]
+    fn erreur_d_ecriture_du_cache_renvoyee() {
+        let fichier = std::env::temp_dir().join(format!("ditherpunk_test_fichier_{}", std::process::id()));
+        fs::write(&fichier, b"").unwrap();
+        let (matrice, erreur) = matrice_void_and_cluster(4, 0, &fichier);
+        assert_eq!(matrice.len(), 4);
+        assert!(erreur.unwrap().to_string().contains(&fichier.display().to_string()));
+        fs::remove_file(&fichier).unwrap();
+    }
+}
@@ -0,0 +1,324 @@
+use image::Rgb;
+
+use crate::erreur::DitherError;
+use crate::utils;
+
+/// Couleurs nommées de la spécification CSS Color Module Level 4
+pub const COULEURS_CSS: &[(&str, Rgb<u8>)] = &[
+    ("aliceblue", Rgb([240, 248, 255])),
+    ("antiquewhite", Rgb([250, 235, 215])),
+    ("aqua", Rgb([0, 255, 255])),
+    ("aquamarine", Rgb([127, 255, 212])),
+    ("azure", Rgb([240, 255, 255])),
+    ("beige", Rgb([245, 245, 220])),
+    ("bisque", Rgb([255, 228, 196])),
+    ("black", Rgb([0, 0, 0])),
+    ("blanchedalmond", Rgb([255, 235, 205])),
+    ("blue", Rgb([0, 0, 255])),
+    ("blueviolet", Rgb([138, 43, 226])),
+    ("brown", Rgb([165, 42, 42])),
+    ("burlywood", Rgb([222, 184, 135])),
+    ("cadetblue", Rgb([95, 158, 160])),
+    ("chartreuse", Rgb([127, 255, 0])),
+    ("chocolate", Rgb([210, 105, 30])),
+    ("coral", Rgb([255, 127, 80])),
+    ("cornflowerblue", Rgb([100, 149, 237])),
+    ("cornsilk", Rgb([255, 248, 220])),
+    ("crimson", Rgb([220, 20, 60])),
+    ("cyan", Rgb([0, 255, 255])),
+    ("darkblue", Rgb([0, 0, 139])),
+    ("darkcyan", Rgb([0, 139, 139])),
+    ("darkgoldenrod", Rgb([184, 134, 11])),
+    ("darkgray", Rgb([169, 169, 169])),
+    ("darkgreen", Rgb([0, 100, 0])),
+    ("darkgrey", Rgb([169, 169, 169])),
+    ("darkkhaki", Rgb([189, 183, 107])),
+    ("darkmagenta", Rgb([139, 0, 139])),
+    ("darkolivegreen", Rgb([85, 107, 47])),
+    ("darkorange", Rgb([255, 140, 0])),
+    ("darkorchid", Rgb([153, 50, 204])),
+    ("darkred", Rgb([139, 0, 0])),
+    ("darksalmon", Rgb([233, 150, 122])),
+    ("darkseagreen", Rgb([143, 188, 143])),
+    ("darkslateblue", Rgb([72, 61, 139])),
+    ("darkslategray", Rgb([47, 79, 79])),
+    ("darkslategrey", Rgb([47, 79, 79])),
+    ("darkturquoise", Rgb([0, 206, 209])),
+    ("darkviolet", Rgb([148, 0, 211])),
+    ("deeppink", Rgb([255, 20, 147])),
+    ("deepskyblue", Rgb([0, 191, 255])),
+    ("dimgray", Rgb([105, 105, 105])),
+    ("dimgrey", Rgb([105, 105, 105])),
+    ("dodgerblue", Rgb([30, 144, 255])),
+    ("firebrick", Rgb([178, 34, 34])),
+    ("floralwhite", Rgb([255, 250, 240])),
+    ("forestgreen", Rgb([34, 139, 34])),
+    ("fuchsia", Rgb([255, 0, 255])),
+    ("gainsboro", Rgb([220, 220, 220])),
+    ("ghostwhite", Rgb([248, 248, 255])),
+    ("gold", Rgb([255, 215, 0])),
+    ("goldenrod", Rgb([218, 165, 32])),
+    ("gray", Rgb([128, 128, 128])),
+    ("green", Rgb([0, 128, 0])),
+    ("greenyellow", Rgb([173, 255, 47])),
+    ("grey", Rgb([128, 128, 128])),
+    ("honeydew", Rgb([240, 255, 240])),
+    ("hotpink", Rgb([255, 105, 180])),
+    ("indianred", Rgb([205, 92, 92])),
+    ("indigo", Rgb([75, 0, 130])),
+    ("ivory", Rgb([255, 255, 240])),
+    ("khaki", Rgb([240, 230, 140])),
+    ("lavender", Rgb([230, 230, 250])),
+    ("lavenderblush", Rgb([255, 240, 245])),
+    ("lawngreen", Rgb([124, 252, 0])),
+    ("lemonchiffon", Rgb([255, 250, 205])),
+    ("lightblue", Rgb([173, 216, 230])),
+    ("lightcoral", Rgb([240, 128, 128])),
+    ("lightcyan", Rgb([224, 255, 255])),
+    ("lightgoldenrodyellow", Rgb([250, 250, 210])),
+    ("lightgray", Rgb([211, 211, 211])),
+    ("lightgreen", Rgb([144, 238, 144])),
+    ("lightgrey", Rgb([211, 211, 211])),
+    ("lightpink", Rgb([255, 182, 193])),
+    ("lightsalmon", Rgb([255, 160, 122])),
+    ("lightseagreen", Rgb([32, 178, 170])),
+    ("lightskyblue", Rgb([135, 206, 250])),
+    ("lightslategray", Rgb([119, 136, 153])),
+    ("lightslategrey", Rgb([119, 136, 153])),
+    ("lightsteelblue", Rgb([176, 196, 222])),
+    ("lightyellow", Rgb([255, 255, 224])),
+    ("lime", Rgb([0, 255, 0])),
+    ("limegreen", Rgb([50, 205, 50])),
+    ("linen", Rgb([250, 240, 230])),
+    ("magenta", Rgb([255, 0, 255])),
+    ("maroon", Rgb([128, 0, 0])),
+    ("mediumaquamarine", Rgb([102, 205, 170])),
+    ("mediumblue", Rgb([0, 0, 205])),
+    ("mediumorchid", Rgb([186, 85, 211])),
+    ("mediumpurple", Rgb([147, 112, 219])),
+    ("mediumseagreen", Rgb([60, 179, 113])),
+    ("mediumslateblue", Rgb([123, 104, 238])),
+    ("mediumspringgreen", Rgb([0, 250, 154])),
+    ("mediumturquoise", Rgb([72, 209, 204])),
+    ("mediumvioletred", Rgb([199, 21, 133])),
+    ("midnightblue", Rgb([25, 25, 112])),
+    ("mintcream", Rgb([245, 255, 250])),
+    ("mistyrose", Rgb([255, 228, 225])),
+    ("moccasin", Rgb([255, 228, 181])),
+    ("navajowhite", Rgb([255, 222, 173])),
+    ("navy", Rgb([0, 0, 128])),
+    ("oldlace", Rgb([253, 245, 230])),
+    ("olive", Rgb([128, 128, 0])),
+    ("olivedrab", Rgb([107, 142, 35])),
+    ("orange", Rgb([255, 165, 0])),
+    ("orangered", Rgb([255, 69, 0])),
+    ("orchid", Rgb([218, 112, 214])),
+    ("palegoldenrod", Rgb([238, 232, 170])),
+    ("palegreen", Rgb([152, 251, 152])),
+    ("paleturquoise", Rgb([175, 238, 238])),
+    ("palevioletred", Rgb([219, 112, 147])),
+    ("papayawhip", Rgb([255, 239, 213])),
+    ("peachpuff", Rgb([255, 218, 185])),
+    ("peru", Rgb([205, 133, 63])),
+    ("pink", Rgb([255, 192, 203])),
+    ("plum", Rgb([221, 160, 221])),
+    ("powderblue", Rgb([176, 224, 230])),
+    ("purple", Rgb([128, 0, 128])),
+    ("rebeccapurple", Rgb([102, 51, 153])),
+    ("red", Rgb([255, 0, 0])),
+    ("rosybrown", Rgb([188, 143, 143])),
+    ("royalblue", Rgb([65, 105, 225])),
+    ("saddlebrown", Rgb([139, 69, 19])),
+    ("salmon", Rgb([250, 128, 114])),
+    ("sandybrown", Rgb([244, 164, 96])),
+    ("seagreen", Rgb([46, 139, 87])),
+    ("seashell", Rgb([255, 245, 238])),
+    ("sienna", Rgb([160, 82, 45])),
+    ("silver", Rgb([192, 192, 192])),
+    ("skyblue", Rgb([135, 206, 235])),
+    ("slateblue", Rgb([106, 90, 205])),
+    ("slategray", Rgb([112, 128, 144])),
+    ("slategrey", Rgb([112, 128, 144])),
+    ("snow", Rgb([255, 250, 250])),
+    ("springgreen", Rgb([0, 255, 127])),
+    ("steelblue", Rgb([70, 130, 180])),
+    ("tan", Rgb([210, 180, 140])),
+    ("teal", Rgb([0, 128, 128])),
+    ("thistle", Rgb([216, 191, 216])),
+    ("tomato", Rgb([255, 99, 71])),
+    ("turquoise", Rgb([64, 224, 208])),
+    ("violet", Rgb([238, 130, 238])),
+    ("wheat", Rgb([245, 222, 179])),
+    ("white", Rgb([255, 255, 255])),
+    ("whitesmoke", Rgb([245, 245, 245])),
+    ("yellow", Rgb([255, 255, 0])),
+    ("yellowgreen", Rgb([154, 205, 50])),
+];
+
+/// Rechercher une couleur nommée CSS (insensible à la casse)
+pub fn couleur_css(nom: &str) -> Option<Rgb<u8>> {
+    COULEURS_CSS
+        .iter()
+        .find(|(nom_css, _)| nom_css.eq_ignore_ascii_case(nom))
+        .map(|(_, rgb)| *rgb)
+}
+
+/// Convertir une couleur hexadécimale à 3 ou 6 chiffres. Le `#` n'est facultatif que si le code
+/// contient une lettre : `123` ou `000000` seraient sinon confondus avec des composantes décimales
+fn parser_hexadecimal(texte: &str) -> Option<Rgb<u8>> {
+    let hex = match texte.strip_prefix('#') {
+        Some(hex) => hex,
+        None if texte.chars().all(|c| c.is_ascii_digit()) => return None,
+        None => texte,
+    };
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    match hex.len() {
+        3 => {
+            let mut composantes = [0u8; 3];
+            for (i, c) in hex.chars().enumerate() {
+                let valeur = c.to_digit(16)? as u8;
+                composantes[i] = valeur * 17; // #abc équivaut à #aabbcc
+            }
+            Some(Rgb(composantes))
+        }
+        6 => {
+            let mut composantes = [0u8; 3];
+            for (i, composante) in composantes.iter_mut().enumerate() {
+                *composante = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?;
+            }
+            Some(Rgb(composantes))
+        }
+        _ => None,
+    }
+}
+
+/// Convertir un triplet `r,g,b` ou `rgb(r,g,b)` en couleur
+fn parser_triplet(texte: &str) -> Option<Rgb<u8>> {
+    let triplet = texte
+        .strip_prefix("rgb(")
+        .and_then(|reste| reste.strip_suffix(')'))
+        .unwrap_or(texte);
+    let composantes: Vec<&str> = triplet.split(',').map(str::trim).collect();
+    if composantes.len() != 3 {
+        return None;
+    }
+    let mut rgb = [0u8; 3];
+    for (valeur, composante) in rgb.iter_mut().zip(composantes) {
+        *valeur = composante.parse().ok()?;
+    }
+    Some(Rgb(rgb))
+}
+
+/// Convertir une couleur saisie par l'utilisateur : nom de la liste principale
+/// (`noir`, `rouge`…), nom CSS (`rebeccapurple`), hexadécimal (`#1d2b53`, `#fca`)
+/// ou triplet (`255,204,170`, `rgb(255,204,170)`)
+pub fn parser_couleur(texte: &str) -> Result<Rgb<u8>, DitherError> {
+    let texte = texte.trim();
+    let nom = texte.to_lowercase();
+    if let Ok(rgb) = utils::obtenir_couleur_par_nom(&nom, &utils::creer_liste_couleurs()) {
+        return Ok(rgb);
+    }
+    couleur_css(&nom)
+        .or_else(|| parser_hexadecimal(&nom))
+        .or_else(|| parser_triplet(&nom.replace(' ', "")))
+        .ok_or_else(|| DitherError::CouleurInconnue(texte.to_string()))
+}
+
+/// Convertir une liste de couleurs séparées par des virgules (`noir,rouge,#ffcc00`).
+/// Les virgules à l'intérieur de `rgb(…)` ne séparent pas les couleurs, et trois nombres
+/// consécutifs forment un triplet (`0,0,0,blanc` donne noir puis blanc).
+pub fn parser_liste_couleurs(texte: &str) -> Result<Vec<Rgb<u8>>, DitherError> {
+    let mut elements = vec![];
+    let mut profondeur = 0;
+    let mut debut = 0;
+    for (i, c) in texte.char_indices() {
+        match c {
+            '(' => profondeur += 1,
+            ')' => profondeur -= 1,
+            ',' if profondeur == 0 => {
+                elements.push(&texte[debut..i]);
+                debut = i + 1;
+            }
+            _ => {}
+        }
+    }
+    elements.push(&texte[debut..]);
+
+    let est_nombre = |element: &str| {
+        let element = element.trim();
+        !element.is_empty() && element.chars().all(|c| c.is_ascii_digit())
+    };
+    let mut couleurs = vec![];
+    let mut i = 0;
+    while i < elements.len() {
+        if !est_nombre(elements[i]) {
+            couleurs.push(parser_couleur(elements[i])?);
+            i += 1;
+            continue;
+        }
+        let triplet = elements.get(i..i + 3).filter(|triplet| triplet.iter().all(|element| est_nombre(element)));
+        let Some(triplet) = triplet else {
+            let reste = elements[i..elements.len().min(i + 3)].join(",");
+            return Err(DitherError::CouleurInconnue(reste));
+        };
+        couleurs.push(parser_couleur(&triplet.join(","))?);
+        i += 3;
+    }
+    Ok(couleurs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parser_couleur_hexadecimale() {
+        assert_eq!(parser_couleur("#1d2b53").unwrap(), Rgb([0x1d, 0x2b, 0x53]));
+        assert_eq!(parser_couleur("1D2B53").unwrap(), Rgb([0x1d, 0x2b, 0x53]));
+        assert_eq!(parser_couleur("#000000").unwrap(), Rgb([0, 0, 0]));
+        assert_eq!(parser_couleur("#fca").unwrap(), Rgb([0xff, 0xcc, 0xaa]));
+    }
+
+    #[test]
+    fn parser_couleur_triplet() {
+        assert_eq!(parser_couleur("255,204,170").unwrap(), Rgb([255, 204, 170]));
+        assert_eq!(parser_couleur("rgb(255, 204, 170)").unwrap(), Rgb([255, 204, 170]));
+    }
+
+    #[test]
+    fn parser_couleur_nommee() {
+        assert_eq!(parser_couleur("noir").unwrap(), Rgb([0, 0, 0]));
+        assert_eq!(parser_couleur("RebeccaPurple").unwrap(), Rgb([102, 51, 153]));
+        assert_eq!(parser_couleur("  white ").unwrap(), Rgb([255, 255, 255]));
+    }
+
+    #[test]
+    fn parser_couleur_refuse_les_valeurs_invalides() {
+        for texte in ["#12345", "#ggg", "000000", "123", "256,0,0", "1,2", "1,2,3,4", "rgb(1,2,3", "pasunecouleur", "", "é"] {
+            assert!(
+                matches!(parser_couleur(texte), Err(DitherError::CouleurInconnue(_))),
+                "'{}' aurait dû être refusée",
+                texte
+            );
+        }
+    }
+
+    #[test]
+    fn parser_liste_couleurs_garde_les_triplets_entiers() {
+        let couleurs = parser_liste_couleurs("noir,rgb(1,2,3),#fff").unwrap();
+        assert_eq!(couleurs, vec![Rgb([0, 0, 0]), Rgb([1, 2, 3]), Rgb([255, 255, 255])]);
+        assert!(parser_liste_couleurs("noir,,blanc").is_err());
+    }
+
+    #[test]
+    fn parser_liste_couleurs_regroupe_les_triplets_nus() {
+        assert_eq!(parser_liste_couleurs("255,204,170").unwrap(), vec![Rgb([255, 204, 170])]);
+        assert_eq!(parser_liste_couleurs("0,0,0").unwrap(), vec![Rgb([0, 0, 0])]);
+        let couleurs = parser_liste_couleurs("blanc, 0, 0, 0,#abc,1,2,3").unwrap();
+        assert_eq!(couleurs, vec![Rgb([255, 255, 255]), Rgb([0, 0, 0]), Rgb([0xaa, 0xbb, 0xcc]), Rgb([1, 2, 3])]);
+        assert!(matches!(parser_liste_couleurs("0,0,noir"), Err(DitherError::CouleurInconnue(_))));
+        assert!(matches!(parser_liste_couleurs("1,2,3,4"), Err(DitherError::CouleurInconnue(_))));
+    }
+}
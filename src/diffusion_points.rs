@@ -0,0 +1,257 @@
+use std::str::FromStr;
+
+use image::{Rgb, RgbImage};
+
+use crate::metrique::MetriqueCouleur;
+use crate::utils;
+
+/// Matrice de classes 8x8 publiée par Knuth (1987)
+const CLASSES_KNUTH: [[usize; 8]; 8] = [
+    [34, 48, 40, 32, 29, 15, 23, 31],
+    [42, 58, 56, 53, 21, 5, 7, 10],
+    [50, 62, 61, 45, 13, 1, 2, 18],
+    [38, 46, 54, 37, 25, 17, 9, 26],
+    [28, 14, 22, 30, 35, 49, 41, 33],
+    [20, 4, 6, 11, 43, 59, 57, 52],
+    [12, 0, 3, 19, 51, 63, 60, 44],
+    [24, 16, 8, 27, 39, 47, 55, 36],
+];
+
+/// Matrice de Knuth retouchée par `optimiser_classes` (voir les tests, 5000 essais, graine 0),
+/// précalculée pour ne pas refaire la recherche à chaque exécution
+const CLASSES_KNUTH_RETOUCHEES: [[usize; 8]; 8] = [
+    [34, 48, 19, 32, 29, 15, 23, 31],
+    [42, 57, 56, 4, 21, 5, 7, 10],
+    [44, 49, 61, 55, 13, 1, 2, 18],
+    [54, 52, 62, 37, 25, 17, 9, 26],
+    [28, 14, 22, 24, 35, 38, 41, 33],
+    [20, 53, 6, 11, 8, 16, 58, 45],
+    [12, 0, 3, 40, 51, 59, 50, 60],
+    [30, 46, 43, 39, 27, 47, 63, 36],
+];
+
+/// Matrice de classes de la diffusion par points
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ClassesDiffusionPoints {
+    /// Matrice d'origine de Knuth
+    #[default]
+    Knuth,
+    /// Matrice de Knuth retouchée par une recherche gloutonne maison (échanges aléatoires de classes
+    /// conservés s'ils réduisent l'erreur perçue sur des aplats de gris). Ce n'est pas l'optimisation
+    /// de Mese et Vaidyanathan : leurs matrices publiées ne sont pas fournies
+    KnuthRetouchee,
+}
+
+// Implémentation de FromStr pour Enum
+impl FromStr for ClassesDiffusionPoints {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "knuth" => Ok(ClassesDiffusionPoints::Knuth),
+            "knuth-retouchee" | "knuthretouchee" => Ok(ClassesDiffusionPoints::KnuthRetouchee),
+            _ => Err(format!("Matrice de classes invalide: {}", s)),
+        }
+    }
+}
+
+impl ClassesDiffusionPoints {
+    /// Récupérer la matrice de classes (indexée par `matrice[y][x]`)
+    pub fn matrice(&self) -> Vec<Vec<usize>> {
+        match self {
+            ClassesDiffusionPoints::Knuth => CLASSES_KNUTH.iter().map(|ligne| ligne.to_vec()).collect(),
+            ClassesDiffusionPoints::KnuthRetouchee => CLASSES_KNUTH_RETOUCHEES.iter().map(|ligne| ligne.to_vec()).collect(),
+        }
+    }
+}
+
+/// Voisins d'un pixel et poids de diffusion : 2 pour les voisins directs, 1 pour les diagonales
+const VOISINS: [(i32, i32, f32); 8] = [
+    (-1, -1, 1.0),
+    (0, -1, 2.0),
+    (1, -1, 1.0),
+    (-1, 0, 2.0),
+    (1, 0, 2.0),
+    (-1, 1, 1.0),
+    (0, 1, 2.0),
+    (1, 1, 1.0),
+];
+
+/// Pixels de l'image regroupés par classe, dans l'ordre croissant des classes
+fn pixels_par_classe(largeur: usize, hauteur: usize, classes: &[Vec<usize>]) -> Vec<Vec<(usize, usize)>> {
+    let (hauteur_classes, largeur_classes) = (classes.len(), classes[0].len());
+    let mut groupes = vec![Vec::new(); hauteur_classes * largeur_classes];
+    for y in 0..hauteur {
+        for x in 0..largeur {
+            groupes[classes[y % hauteur_classes][x % largeur_classes]].push((x, y));
+        }
+    }
+    groupes
+}
+
+/// Moteur de la diffusion par points : les pixels sont traités classe par classe et chacun répartit
+/// son erreur entre ses voisins de classe supérieure, qui ne sont pas encore traités. Les pixels d'une
+/// même classe ne dépendent pas les uns des autres, d'où un traitement parallélisable par classe.
+/// `torique` fait boucler les bords de l'image, ce qui sert à évaluer les matrices sur des aplats
+fn diffuser_points<F>(
+    tampon: &mut [[f32; 3]],
+    largeur: usize,
+    hauteur: usize,
+    classes: &[Vec<usize>],
+    torique: bool,
+    mut quantifier: F,
+) where
+    F: FnMut(usize, &[f32; 3]) -> [f32; 3],
+{
+    let (hauteur_classes, largeur_classes) = (classes.len(), classes[0].len());
+    for (classe, pixels) in pixels_par_classe(largeur, hauteur, classes).into_iter().enumerate() {
+        for (x, y) in pixels {
+            let indice = y * largeur + x;
+            let ancien_pixel = tampon[indice];
+            let nouveau_pixel = quantifier(indice, &ancien_pixel);
+
+            let mut voisins = [(0usize, 0.0f32); 8];
+            let mut nombre = 0;
+            let mut total = 0.0;
+            for (dx, dy, poids) in VOISINS {
+                let (mut vx, mut vy) = (x as i32 + dx, y as i32 + dy);
+                if torique {
+                    vx = vx.rem_euclid(largeur as i32);
+                    vy = vy.rem_euclid(hauteur as i32);
+                } else if vx < 0 || vy < 0 || vx >= largeur as i32 || vy >= hauteur as i32 {
+                    continue;
+                }
+                let (vx, vy) = (vx as usize, vy as usize);
+                if classes[vy % hauteur_classes][vx % largeur_classes] > classe {
+                    voisins[nombre] = (vy * largeur + vx, poids);
+                    nombre += 1;
+                    total += poids;
+                }
+            }
+            // Sans voisin de classe supérieure, l'erreur est perdue
+            for &(voisin, poids) in &voisins[..nombre] {
+                for c in 0..3 {
+                    tampon[voisin][c] += (ancien_pixel[c] - nouveau_pixel[c]) * poids / total;
+                }
+            }
+        }
+    }
+}
+
+/// Diffusion par points de Knuth vers la couleur de la palette la plus proche selon la métrique
+pub fn diffusion_points(image_rgb8: &mut RgbImage, couleurs_palette: &[Rgb<u8>], metrique: MetriqueCouleur, classes: &[Vec<usize>]) {
+    if couleurs_palette.is_empty() {
+        return;
+    }
+    let (largeur, hauteur) = (image_rgb8.width() as usize, image_rgb8.height() as usize);
+    let coordonnees_palette: Vec<[f32; 3]> = couleurs_palette.iter().map(|couleur| metrique.coordonnees(couleur)).collect();
+    let mut tampon: Vec<[f32; 3]> = image_rgb8.pixels().map(|pixel| pixel.0.map(|c| c as f32)).collect();
+    let mut sortie = vec![Rgb([0, 0, 0]); largeur * hauteur];
+
+    diffuser_points(&mut tampon, largeur, hauteur, classes, false, |indice, pixel| {
+        let (couleur, valeur) = utils::quantifier_srgb(pixel, couleurs_palette, &coordonnees_palette, metrique);
+        sortie[indice] = couleur;
+        valeur
+    });
+    for (pixel, couleur) in image_rgb8.pixels_mut().zip(sortie) {
+        *pixel = couleur;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    /// Côté des aplats de gris servant à évaluer une matrice de classes (multiple de 8)
+    const COTE_EVALUATION: usize = 32;
+
+    /// Erreur perçue d'une matrice de classes : somme, sur plusieurs aplats de gris tramés en noir et blanc,
+    /// du carré de l'écart au gris après filtrage gaussien (modèle simple de la vision)
+    fn erreur_percue(classes: &[Vec<usize>], filtre: &[Vec<f32>]) -> f32 {
+        let cote = COTE_EVALUATION;
+        let rayon = filtre.len() as i32 / 2;
+        let mut erreur = 0.0;
+        for niveau in 1..16 {
+            let gris = niveau as f32 / 16.0;
+            let mut tampon = vec![[gris; 3]; cote * cote];
+            let mut ecarts = vec![0.0f32; cote * cote];
+            diffuser_points(&mut tampon, cote, cote, classes, true, |indice, pixel| {
+                let sortie = if pixel[0] >= 0.5 { 1.0 } else { 0.0 };
+                ecarts[indice] = sortie - gris;
+                [sortie; 3]
+            });
+            for y in 0..cote as i32 {
+                for x in 0..cote as i32 {
+                    let mut filtre_pixel = 0.0;
+                    for (i, ligne) in filtre.iter().enumerate() {
+                        for (j, poids) in ligne.iter().enumerate() {
+                            let vx = (x + j as i32 - rayon).rem_euclid(cote as i32) as usize;
+                            let vy = (y + i as i32 - rayon).rem_euclid(cote as i32) as usize;
+                            filtre_pixel += poids * ecarts[vy * cote + vx];
+                        }
+                    }
+                    erreur += filtre_pixel * filtre_pixel;
+                }
+            }
+        }
+        erreur
+    }
+
+    /// Filtre gaussien 7x7 d'écart type 1.5 pixel
+    fn filtre_vision() -> Vec<Vec<f32>> {
+        (-3..=3)
+            .map(|dy: i32| (-3..=3).map(|dx: i32| (-((dx * dx + dy * dy) as f32) / (2.0 * 1.5 * 1.5)).exp()).collect())
+            .collect()
+    }
+
+    /// Optimiser une matrice de classes par échanges aléatoires de deux classes, conservés seulement
+    /// s'ils réduisent l'erreur perçue. Sert à régénérer [`CLASSES_KNUTH_RETOUCHEES`]
+    fn optimiser_classes(mut classes: Vec<Vec<usize>>, essais: usize, graine: u64) -> Vec<Vec<usize>> {
+        let filtre = filtre_vision();
+        let (hauteur, largeur) = (classes.len(), classes[0].len());
+        let mut rng = StdRng::seed_from_u64(graine);
+        let mut meilleure = erreur_percue(&classes, &filtre);
+        for _ in 0..essais {
+            let (y1, x1) = (rng.gen_range(0..hauteur), rng.gen_range(0..largeur));
+            let (y2, x2) = (rng.gen_range(0..hauteur), rng.gen_range(0..largeur));
+            let (a, b) = (classes[y1][x1], classes[y2][x2]);
+            classes[y1][x1] = b;
+            classes[y2][x2] = a;
+            let erreur = erreur_percue(&classes, &filtre);
+            if erreur < meilleure {
+                meilleure = erreur;
+            } else {
+                classes[y1][x1] = a;
+                classes[y2][x2] = b;
+            }
+        }
+        classes
+    }
+
+    /// Vérifier que la matrice contient chaque classe exactement une fois
+    fn est_permutation(classes: &[Vec<usize>]) -> bool {
+        let mut valeurs: Vec<usize> = classes.iter().flatten().copied().collect();
+        valeurs.sort_unstable();
+        valeurs == (0..64).collect::<Vec<_>>()
+    }
+
+    #[test]
+    fn optimiser_classes_conserve_une_permutation_sans_degrader() {
+        let knuth = ClassesDiffusionPoints::Knuth.matrice();
+        let filtre = filtre_vision();
+        let retouchee = optimiser_classes(knuth.clone(), 20, 1);
+        assert!(est_permutation(&retouchee));
+        assert!(erreur_percue(&retouchee, &filtre) <= erreur_percue(&knuth, &filtre) + 1e-3);
+    }
+
+    #[test]
+    fn matrice_retouchee_meilleure_que_knuth() {
+        let retouchee = ClassesDiffusionPoints::KnuthRetouchee.matrice();
+        assert!(est_permutation(&retouchee));
+        let filtre = filtre_vision();
+        assert!(erreur_percue(&retouchee, &filtre) < erreur_percue(&ClassesDiffusionPoints::Knuth.matrice(), &filtre));
+    }
+}
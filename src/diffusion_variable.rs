@@ -0,0 +1,263 @@
+use std::str::FromStr;
+
+use image::{Rgb, RgbImage};
+use rand::Rng;
+
+use crate::metrique::MetriqueCouleur;
+use crate::utils;
+
+/// Coefficients d'Ostromoukhov (2001) pour les niveaux d'entrée 0 à 127 : poids du pixel de droite,
+/// du pixel en bas à gauche et du pixel du dessous. Les niveaux 128 à 255 utilisent ceux du niveau
+/// symétrique `255 - niveau`
+const COEFFICIENTS_OSTROMOUKHOV: [[u32; 3]; 128] = [
+    [13, 0, 5],
+    [13, 0, 5],
+    [21, 0, 10],
+    [7, 0, 4],
+    [8, 0, 5],
+    [47, 3, 28],
+    [23, 3, 13],
+    [15, 3, 8],
+    [22, 6, 11],
+    [43, 15, 20],
+    [7, 3, 3],
+    [501, 224, 211],
+    [249, 116, 103],
+    [165, 80, 67],
+    [123, 62, 49],
+    [489, 256, 191],
+    [81, 44, 31],
+    [483, 272, 181],
+    [60, 35, 22],
+    [53, 32, 19],
+    [237, 148, 83],
+    [471, 304, 161],
+    [3, 2, 1],
+    [481, 314, 185],
+    [354, 226, 155],
+    [1389, 866, 685],
+    [227, 138, 125],
+    [267, 158, 163],
+    [327, 188, 220],
+    [61, 34, 45],
+    [627, 338, 505],
+    [1227, 638, 1075],
+    [20, 10, 19],
+    [1937, 1000, 1767],
+    [977, 520, 855],
+    [657, 360, 551],
+    [71, 40, 57],
+    [2005, 1160, 1539],
+    [337, 200, 247],
+    [2039, 1240, 1425],
+    [257, 160, 171],
+    [691, 440, 437],
+    [1045, 680, 627],
+    [301, 200, 171],
+    [177, 120, 95],
+    [2141, 1480, 1083],
+    [1079, 760, 513],
+    [725, 520, 323],
+    [137, 100, 57],
+    [2209, 1640, 855],
+    [53, 40, 19],
+    [2243, 1720, 741],
+    [565, 440, 171],
+    [759, 600, 209],
+    [1147, 920, 285],
+    [2311, 1880, 513],
+    [97, 80, 19],
+    [335, 280, 57],
+    [1181, 1000, 171],
+    [793, 680, 95],
+    [599, 520, 57],
+    [2413, 2120, 171],
+    [405, 360, 19],
+    [2447, 2200, 57],
+    [11, 10, 0],
+    [158, 151, 3],
+    [178, 179, 7],
+    [1030, 1091, 63],
+    [248, 277, 21],
+    [318, 375, 35],
+    [458, 571, 63],
+    [878, 1159, 147],
+    [5, 7, 1],
+    [172, 181, 37],
+    [97, 76, 22],
+    [72, 41, 17],
+    [119, 47, 29],
+    [4, 1, 1],
+    [4, 1, 1],
+    [4, 1, 1],
+    [4, 1, 1],
+    [4, 1, 1],
+    [4, 1, 1],
+    [4, 1, 1],
+    [4, 1, 1],
+    [4, 1, 1],
+    [65, 18, 17],
+    [95, 29, 26],
+    [185, 62, 53],
+    [30, 11, 9],
+    [35, 14, 11],
+    [85, 37, 28],
+    [55, 26, 19],
+    [80, 41, 29],
+    [155, 86, 59],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+    [5, 3, 2],
+];
+
+/// Niveaux clés de Zhou et Fang (2003) : niveau d'entrée, poids (droite, bas à gauche, bas) et
+/// amplitude de la modulation du seuil. Les niveaux intermédiaires sont interpolés linéairement
+const NIVEAUX_CLES_ZHOU_FANG: [(usize, [f32; 3], f32); 18] = [
+    (0, [13.0, 0.0, 5.0], 0.0),
+    (1, [1300249.0, 0.0, 499250.0], 0.0),
+    (2, [214114.0, 287.0, 99357.0], 0.0),
+    (3, [351854.0, 0.0, 199965.0], 0.0),
+    (4, [801100.0, 0.0, 490999.0], 0.0),
+    (10, [704075.0, 297466.0, 303694.0], 0.0),
+    (22, [46613.0, 31917.0, 21469.0], 0.0),
+    (32, [47482.0, 30617.0, 21900.0], 0.0),
+    (44, [43024.0, 42131.0, 14826.0], 0.34),
+    (64, [36411.0, 43219.0, 20369.0], 0.5),
+    (72, [38477.0, 53843.0, 7678.0], 0.5),
+    (77, [40503.0, 51547.0, 7948.0], 0.5),
+    (85, [35865.0, 34108.0, 30026.0], 1.0),
+    (95, [34117.0, 36899.0, 28983.0], 0.17),
+    (102, [35464.0, 35049.0, 29485.0], 0.5),
+    (107, [16477.0, 18810.0, 14712.0], 0.7),
+    (112, [33360.0, 37954.0, 28685.0], 0.79),
+    (127, [35269.0, 36066.0, 28664.0], 1.0),
+];
+
+/// Jeu de coefficients d'une diffusion d'erreur à coefficients variables
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum CoefficientsVariables {
+    /// Coefficients d'Ostromoukhov, choisis pour supprimer les motifs réguliers à chaque niveau de gris
+    #[default]
+    Ostromoukhov,
+    /// Coefficients de Zhou et Fang, accompagnés d'une modulation aléatoire du seuil qui casse
+    /// les motifs restants dans les tons moyens
+    ZhouFang,
+}
+
+// Implémentation de FromStr pour Enum
+impl FromStr for CoefficientsVariables {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ostromoukhov" => Ok(CoefficientsVariables::Ostromoukhov),
+            "zhou-fang" | "zhoufang" => Ok(CoefficientsVariables::ZhouFang),
+            _ => Err(format!("Coefficients variables invalides: {}", s)),
+        }
+    }
+}
+
+impl CoefficientsVariables {
+    /// Poids normalisés (droite, bas à gauche, bas) et amplitude de modulation du seuil pour un
+    /// niveau d'entrée entre 0 et 255
+    pub fn coefficients(&self, niveau: u8) -> ([f32; 3], f32) {
+        let niveau = (niveau as usize).min(255 - niveau as usize);
+        match self {
+            CoefficientsVariables::Ostromoukhov => (normaliser(COEFFICIENTS_OSTROMOUKHOV[niveau].map(|p| p as f32)), 0.0),
+            CoefficientsVariables::ZhouFang => {
+                let suivant = NIVEAUX_CLES_ZHOU_FANG.partition_point(|&(cle, _, _)| cle < niveau);
+                let (cle_haute, poids_hauts, modulation_haute) = NIVEAUX_CLES_ZHOU_FANG[suivant];
+                if cle_haute == niveau {
+                    return (normaliser(poids_hauts), modulation_haute);
+                }
+                let (cle_basse, poids_bas, modulation_basse) = NIVEAUX_CLES_ZHOU_FANG[suivant - 1];
+                let t = (niveau - cle_basse) as f32 / (cle_haute - cle_basse) as f32;
+                let (poids_bas, poids_hauts) = (normaliser(poids_bas), normaliser(poids_hauts));
+                let poids = std::array::from_fn(|i| poids_bas[i] + (poids_hauts[i] - poids_bas[i]) * t);
+                (poids, modulation_basse + (modulation_haute - modulation_basse) * t)
+            }
+        }
+    }
+
+    /// Matrice de diffusion d'un niveau d'entrée, le pixel courant étant en (0, 1)
+    pub fn matrice(&self, niveau: u8) -> Vec<Vec<f32>> {
+        let ([droite, bas_gauche, bas], _) = self.coefficients(niveau);
+        vec![vec![0.0, 0.0, droite], vec![bas_gauche, bas, 0.0]]
+    }
+}
+
+/// Ramener des poids à une somme de 1
+fn normaliser(poids: [f32; 3]) -> [f32; 3] {
+    let somme: f32 = poids.iter().sum();
+    poids.map(|p| p / somme)
+}
+
+/// Diffusion d'erreur à coefficients variables : chaque pixel diffuse son erreur avec la matrice
+/// associée à sa luminosité dans l'image d'origine, en balayage serpentin comme le prévoient
+/// Ostromoukhov et Zhou-Fang. Avec la modulation du seuil de Zhou-Fang, la valeur comparée aux
+/// couleurs de la palette est décalée d'un tirage aléatoire entre -64 et 64 multiplié par
+/// l'amplitude du niveau : le seuil varie autour de 128 sans que sa moyenne change
+pub fn diffusion_variable(image_rgb8: &mut RgbImage, couleurs_palette: &[Rgb<u8>], metrique: MetriqueCouleur, coefficients: CoefficientsVariables) {
+    if couleurs_palette.is_empty() {
+        return;
+    }
+    let coordonnees_palette: Vec<[f32; 3]> = couleurs_palette.iter().map(|couleur| metrique.coordonnees(couleur)).collect();
+    let matrices: Vec<Vec<Vec<f32>>> = (0..=255).map(|niveau| coefficients.matrice(niveau)).collect();
+    let modulations: Vec<f32> = (0..=255).map(|niveau| coefficients.coefficients(niveau).1).collect();
+    let niveaux: Vec<u8> = image_rgb8
+        .pixels()
+        .map(|pixel| utils::luminosite_pixel(pixel).round().clamp(0.0, 255.0) as u8)
+        .collect();
+    let tampon: Vec<[f32; 3]> = image_rgb8.pixels().map(|pixel| pixel.0.map(|c| c as f32)).collect();
+    let largeur = image_rgb8.width() as usize;
+    let mut rng = rand::thread_rng();
+
+    utils::diffuser_erreur(
+        image_rgb8,
+        tampon,
+        (0, 1),
+        true,
+        |x, y| &matrices[niveaux[y as usize * largeur + x as usize] as usize],
+        |x, y, pixel| {
+            let modulation = modulations[niveaux[y as usize * largeur + x as usize] as usize];
+            if modulation == 0.0 {
+                return utils::quantifier_srgb(pixel, couleurs_palette, &coordonnees_palette, metrique);
+            }
+            let decalage = modulation * rng.gen_range(-64.0..64.0);
+            let module = pixel.map(|c| c - decalage);
+            let (couleur, _) = utils::quantifier_srgb(&module, couleurs_palette, &coordonnees_palette, metrique);
+            // L'erreur reste mesurée par rapport à la valeur non modulée
+            (couleur, couleur.0.map(|c| c as f32))
+        },
+    );
+}
@@ -0,0 +1,68 @@
+use std::fmt;
+
+/// Erreurs pouvant survenir lors du chargement, du traitement ou de la sauvegarde d'une image
+#[derive(Debug)]
+pub enum DitherError {
+    /// Erreur d'entrée/sortie lors de l'accès à un fichier
+    Io(std::io::Error),
+    /// Le fichier d'entrée n'a pas pu être décodé comme une image
+    Decodage(image::ImageError),
+    /// L'image n'a pas pu être encodée dans le fichier de sortie
+    Encodage(image::ImageError),
+    /// La couleur demandée n'est pas disponible
+    CouleurInconnue(String),
+    /// Les coordonnées demandées sont hors des limites de l'image
+    HorsLimites { x: u32, y: u32, largeur: u32, hauteur: u32 },
+    /// La palette ne convient pas à l'algorithme demandé
+    PaletteInvalide(String),
+    /// Le fichier de palette est mal formé (ligne numérotée à partir de 1 pour les formats texte)
+    FormatPalette { ligne: Option<usize>, message: String },
+    /// Le noyau de diffusion d'erreur personnalisé est mal formé
+    NoyauInvalide(String),
+    /// Les arguments de la ligne de commande sont incohérents
+    Arguments(String),
+    /// La matrice de seuils du tramage ordonné est vide, irrégulière ou trop grande
+    MatriceInvalide(String),
+}
+
+impl fmt::Display for DitherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DitherError::Io(err) => write!(f, "Impossible d'accéder au fichier : {}", err),
+            DitherError::Decodage(err) => write!(f, "Impossible de décoder l'image : {}", err),
+            DitherError::Encodage(err) => write!(f, "Impossible d'encoder l'image : {}", err),
+            DitherError::CouleurInconnue(nom) => write!(f, "La couleur '{}' n'est pas disponible.", nom),
+            DitherError::HorsLimites { x, y, largeur, hauteur } => write!(
+                f,
+                "Les coordonnées ({}, {}) sont hors des limites de l'image ({}x{})",
+                x, y, largeur, hauteur
+            ),
+            DitherError::PaletteInvalide(raison) => write!(f, "Palette invalide : {}", raison),
+            DitherError::FormatPalette { ligne: Some(ligne), message } => {
+                write!(f, "Fichier de palette invalide, ligne {} : {}", ligne, message)
+            }
+            DitherError::FormatPalette { ligne: None, message } => {
+                write!(f, "Fichier de palette invalide : {}", message)
+            }
+            DitherError::NoyauInvalide(raison) => write!(f, "Noyau de diffusion invalide : {}", raison),
+            DitherError::Arguments(raison) => write!(f, "Arguments invalides : {}", raison),
+            DitherError::MatriceInvalide(raison) => write!(f, "Matrice de seuils invalide : {}", raison),
+        }
+    }
+}
+
+impl std::error::Error for DitherError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DitherError::Io(err) => Some(err),
+            DitherError::Decodage(err) | DitherError::Encodage(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for DitherError {
+    fn from(err: std::io::Error) -> Self {
+        DitherError::Io(err)
+    }
+}
@@ -0,0 +1,198 @@
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use image::Rgb;
+
+/// Blanc de référence D65 dans l'espace XYZ
+const BLANC_D65: [f32; 3] = [0.95047, 1.0, 1.08883];
+
+/// Décoder une composante sRGB (0..255) en intensité linéaire (0..1)
+pub fn srgb_vers_lineaire(composante: u8) -> f32 {
+    // Table des 256 valeurs possibles, calculée au premier appel
+    static TABLE: OnceLock<[f32; 256]> = OnceLock::new();
+    let table = TABLE.get_or_init(|| {
+        std::array::from_fn(|i| {
+            let c = i as f32 / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        })
+    });
+    table[composante as usize]
+}
+
+/// Encoder une intensité linéaire (0..1) en composante sRGB (0..255)
+pub fn lineaire_vers_srgb(valeur: f32) -> u8 {
+    let v = valeur.clamp(0.0, 1.0);
+    let c = if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+    (c * 255.0).round() as u8
+}
+
+/// Convertir une couleur sRGB en coordonnées CIE XYZ (D65)
+pub fn srgb_vers_xyz(couleur: &Rgb<u8>) -> [f32; 3] {
+    let r = srgb_vers_lineaire(couleur[0]);
+    let g = srgb_vers_lineaire(couleur[1]);
+    let b = srgb_vers_lineaire(couleur[2]);
+    [
+        0.4124 * r + 0.3576 * g + 0.1805 * b,
+        0.2126 * r + 0.7152 * g + 0.0722 * b,
+        0.0193 * r + 0.1192 * g + 0.9505 * b,
+    ]
+}
+
+/// Convertir des coordonnées CIE XYZ (D65) en couleur sRGB
+pub fn xyz_vers_srgb(xyz: [f32; 3]) -> Rgb<u8> {
+    let [x, y, z] = xyz;
+    let r = 3.2406 * x - 1.5372 * y - 0.4986 * z;
+    let g = -0.9689 * x + 1.8758 * y + 0.0415 * z;
+    let b = 0.0557 * x - 0.2040 * y + 1.0570 * z;
+    Rgb([lineaire_vers_srgb(r), lineaire_vers_srgb(g), lineaire_vers_srgb(b)])
+}
+
+/// Convertir une couleur sRGB en coordonnées CIELAB (L entre 0 et 100)
+pub fn srgb_vers_lab(couleur: &Rgb<u8>) -> [f32; 3] {
+    let f = |t: f32| {
+        if t > 216.0 / 24389.0 {
+            t.cbrt()
+        } else {
+            (24389.0 / 27.0 * t + 16.0) / 116.0
+        }
+    };
+    let xyz = srgb_vers_xyz(couleur);
+    let fx = f(xyz[0] / BLANC_D65[0]);
+    let fy = f(xyz[1] / BLANC_D65[1]);
+    let fz = f(xyz[2] / BLANC_D65[2]);
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+/// Convertir des coordonnées CIELAB en couleur sRGB
+pub fn lab_vers_srgb(lab: [f32; 3]) -> Rgb<u8> {
+    let f_inv = |t: f32| {
+        if t.powi(3) > 216.0 / 24389.0 {
+            t.powi(3)
+        } else {
+            (116.0 * t - 16.0) * 27.0 / 24389.0
+        }
+    };
+    let fy = (lab[0] + 16.0) / 116.0;
+    let fx = fy + lab[1] / 500.0;
+    let fz = fy - lab[2] / 200.0;
+    xyz_vers_srgb([
+        f_inv(fx) * BLANC_D65[0],
+        f_inv(fy) * BLANC_D65[1],
+        f_inv(fz) * BLANC_D65[2],
+    ])
+}
+
+/// Convertir une couleur sRGB en coordonnées OKLab
+pub fn srgb_vers_oklab(couleur: &Rgb<u8>) -> [f32; 3] {
+    let r = srgb_vers_lineaire(couleur[0]);
+    let g = srgb_vers_lineaire(couleur[1]);
+    let b = srgb_vers_lineaire(couleur[2]);
+    lineaire_vers_oklab([r, g, b])
+}
+
+/// Convertir une couleur RGB linéaire (0..1) en coordonnées OKLab
+#[allow(clippy::excessive_precision)] // coefficients publiés par Björn Ottosson
+pub fn lineaire_vers_oklab(rgb: [f32; 3]) -> [f32; 3] {
+    let [r, g, b] = rgb;
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ]
+}
+
+/// Convertir des coordonnées OKLab en couleur RGB linéaire (non bornée)
+#[allow(clippy::excessive_precision)] // coefficients publiés par Björn Ottosson
+pub fn oklab_vers_lineaire(oklab: [f32; 3]) -> [f32; 3] {
+    let [l, a, b] = oklab;
+    let l_ = (l + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m_ = (l - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s_ = (l - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+    [
+        4.0767416621 * l_ - 3.3077115913 * m_ + 0.2309699292 * s_,
+        -1.2684380046 * l_ + 2.6097574011 * m_ - 0.3413193965 * s_,
+        -0.0041960863 * l_ - 0.7034186147 * m_ + 1.7076147010 * s_,
+    ]
+}
+
+/// Convertir des coordonnées OKLab en couleur sRGB
+pub fn oklab_vers_srgb(oklab: [f32; 3]) -> Rgb<u8> {
+    let [r, g, b] = oklab_vers_lineaire(oklab);
+    Rgb([lineaire_vers_srgb(r), lineaire_vers_srgb(g), lineaire_vers_srgb(b)])
+}
+
+/// Convertir une couleur sRGB en densités d'encre cyan, magenta, jaune et noir (0..1).
+/// `gcr` est la part du gris commun aux trois encres remplacée par du noir, `ucr` la part
+/// de ce noir retirée des encres colorées (1 pour un remplacement complet)
+pub fn srgb_vers_cmjn(couleur: &Rgb<u8>, gcr: f32, ucr: f32) -> [f32; 4] {
+    let [c, m, j] = couleur.0.map(|composante| 1.0 - composante as f32 / 255.0);
+    let noir = gcr * c.min(m).min(j);
+    let retrait = ucr * noir;
+    [c - retrait, m - retrait, j - retrait, noir]
+}
+
+/// Espace colorimétrique dans lequel les couleurs sont comparées ou moyennées
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum EspaceCouleur {
+    /// Composantes sRGB (0..255) telles que stockées dans l'image
+    #[default]
+    Srgb,
+    /// Intensités RGB linéaires (0..1), sans la courbe gamma sRGB
+    Lineaire,
+    /// CIELAB (D65)
+    Lab,
+    /// OKLab
+    Oklab,
+}
+
+impl EspaceCouleur {
+    /// Coordonnées d'une couleur sRGB dans cet espace
+    pub fn depuis_srgb(&self, couleur: &Rgb<u8>) -> [f32; 3] {
+        match self {
+            EspaceCouleur::Srgb => [couleur[0] as f32, couleur[1] as f32, couleur[2] as f32],
+            EspaceCouleur::Lineaire => [
+                srgb_vers_lineaire(couleur[0]),
+                srgb_vers_lineaire(couleur[1]),
+                srgb_vers_lineaire(couleur[2]),
+            ],
+            EspaceCouleur::Lab => srgb_vers_lab(couleur),
+            EspaceCouleur::Oklab => srgb_vers_oklab(couleur),
+        }
+    }
+
+    /// Couleur sRGB correspondant à des coordonnées de cet espace
+    pub fn vers_srgb(&self, coordonnees: [f32; 3]) -> Rgb<u8> {
+        match self {
+            EspaceCouleur::Srgb => Rgb(coordonnees.map(|c| c.round().clamp(0.0, 255.0) as u8)),
+            EspaceCouleur::Lineaire => Rgb(coordonnees.map(lineaire_vers_srgb)),
+            EspaceCouleur::Lab => lab_vers_srgb(coordonnees),
+            EspaceCouleur::Oklab => oklab_vers_srgb(coordonnees),
+        }
+    }
+}
+
+// Implémentation de FromStr pour Enum
+impl FromStr for EspaceCouleur {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "rgb" | "srgb" => Ok(EspaceCouleur::Srgb),
+            "lineaire" => Ok(EspaceCouleur::Lineaire),
+            "lab" => Ok(EspaceCouleur::Lab),
+            "oklab" => Ok(EspaceCouleur::Oklab),
+            _ => Err(format!("Espace de couleur invalide: {}", s)),
+        }
+    }
+}
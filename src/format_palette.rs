@@ -0,0 +1,454 @@
+use std::path::Path;
+
+use image::Rgb;
+
+use crate::erreur::DitherError;
+use crate::espaces_couleur;
+
+/// Charger une palette depuis un fichier, le format étant déduit de l'extension :
+/// GIMP (`.gpl`), Adobe Color Table (`.act`), Adobe Swatch Exchange (`.ase`),
+/// Paint.NET (`.txt`) ou JASC (`.pal`)
+pub fn charger_palette(chemin: &str) -> Result<Vec<Rgb<u8>>, DitherError> {
+    let extension = extension_minuscule(chemin);
+
+    let couleurs = match extension.as_str() {
+        "gpl" => parser_gpl(&std::fs::read_to_string(chemin)?)?,
+        "act" => parser_act(&std::fs::read(chemin)?)?,
+        "ase" => parser_ase(&std::fs::read(chemin)?)?,
+        "txt" => parser_paint_net(&std::fs::read_to_string(chemin)?)?,
+        "pal" => parser_jasc_pal(&std::fs::read_to_string(chemin)?)?,
+        _ => {
+            return Err(erreur_fichier(format!(
+                "extension de fichier de palette non reconnue : '{}'",
+                extension
+            )))
+        }
+    };
+
+    if couleurs.is_empty() {
+        return Err(erreur_fichier(format!("le fichier '{}' ne contient aucune couleur", chemin)));
+    }
+    Ok(couleurs)
+}
+
+/// Extension du fichier en minuscules (vide si absente)
+fn extension_minuscule(chemin: &str) -> String {
+    Path::new(chemin)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Erreur de format située à une ligne donnée (numérotée à partir de 1)
+fn erreur_ligne(ligne: usize, message: String) -> DitherError {
+    DitherError::FormatPalette { ligne: Some(ligne + 1), message }
+}
+
+/// Erreur de format qui ne concerne pas une ligne particulière
+fn erreur_fichier(message: String) -> DitherError {
+    DitherError::FormatPalette { ligne: None, message }
+}
+
+/// Lire trois composantes décimales `r g b` au début d'une ligne
+fn parser_composantes(ligne: &str, numero: usize) -> Result<Rgb<u8>, DitherError> {
+    let composantes: Vec<&str> = ligne.split_whitespace().take(3).collect();
+    if composantes.len() < 3 {
+        return Err(erreur_ligne(numero, format!("trois composantes attendues : '{}'", ligne)));
+    }
+    let mut rgb = [0u8; 3];
+    for (valeur, composante) in rgb.iter_mut().zip(composantes) {
+        *valeur = composante
+            .parse()
+            .map_err(|_| erreur_ligne(numero, format!("composante invalide : '{}'", composante)))?;
+    }
+    Ok(Rgb(rgb))
+}
+
+/// Palette GIMP : en-tête `GIMP Palette`, puis une couleur `r g b [nom]` par ligne
+pub fn parser_gpl(texte: &str) -> Result<Vec<Rgb<u8>>, DitherError> {
+    let mut lignes = texte.lines().enumerate();
+    match lignes.next() {
+        Some((_, entete)) if entete.trim() == "GIMP Palette" => {}
+        _ => return Err(erreur_ligne(0, "en-tête 'GIMP Palette' attendu".to_string())),
+    }
+
+    let mut couleurs = vec![];
+    for (numero, ligne) in lignes {
+        let ligne = ligne.trim();
+        if ligne.is_empty()
+            || ligne.starts_with('#')
+            || ligne.starts_with("Name:")
+            || ligne.starts_with("Columns:")
+        {
+            continue;
+        }
+        couleurs.push(parser_composantes(ligne, numero)?);
+    }
+    Ok(couleurs)
+}
+
+/// Palette JASC (Paint Shop Pro) : `JASC-PAL`, version `0100`, nombre de couleurs puis `r g b` par ligne
+pub fn parser_jasc_pal(texte: &str) -> Result<Vec<Rgb<u8>>, DitherError> {
+    let lignes: Vec<&str> = texte.lines().map(str::trim).collect();
+    if lignes.first() != Some(&"JASC-PAL") {
+        return Err(erreur_ligne(0, "en-tête 'JASC-PAL' attendu".to_string()));
+    }
+    if lignes.get(1) != Some(&"0100") {
+        return Err(erreur_ligne(1, "version '0100' attendue".to_string()));
+    }
+    let nombre: usize = lignes
+        .get(2)
+        .and_then(|ligne| ligne.parse().ok())
+        .ok_or_else(|| erreur_ligne(2, "nombre de couleurs attendu".to_string()))?;
+
+    let mut couleurs = vec![];
+    for (numero, ligne) in lignes.iter().enumerate().skip(3) {
+        if ligne.is_empty() {
+            continue;
+        }
+        couleurs.push(parser_composantes(ligne, numero)?);
+    }
+    if couleurs.len() != nombre {
+        return Err(erreur_fichier(format!(
+            "{} couleurs annoncées dans l'en-tête mais {} trouvées",
+            nombre,
+            couleurs.len()
+        )));
+    }
+    Ok(couleurs)
+}
+
+/// Palette Paint.NET : une couleur `AARRGGBB` (ou `RRGGBB`) hexadécimale par ligne, commentaires après `;`
+pub fn parser_paint_net(texte: &str) -> Result<Vec<Rgb<u8>>, DitherError> {
+    let mut couleurs = vec![];
+    for (numero, ligne) in texte.lines().enumerate() {
+        let ligne = ligne.trim();
+        if ligne.is_empty() || ligne.starts_with(';') {
+            continue;
+        }
+        // Vérifier les chiffres avant de découper la ligne par octets
+        if !ligne.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(erreur_ligne(numero, format!("couleur hexadécimale invalide : '{}'", ligne)));
+        }
+        let hex = match ligne.len() {
+            8 => &ligne[2..], // canal alpha ignoré
+            6 => ligne,
+            _ => return Err(erreur_ligne(numero, format!("couleur AARRGGBB attendue : '{}'", ligne))),
+        };
+        let valeur = u32::from_str_radix(hex, 16)
+            .map_err(|_| erreur_ligne(numero, format!("couleur hexadécimale invalide : '{}'", ligne)))?;
+        couleurs.push(Rgb([(valeur >> 16) as u8, (valeur >> 8) as u8, valeur as u8]));
+    }
+    Ok(couleurs)
+}
+
+/// Palette Adobe Color Table : 256 triplets RGB, éventuellement suivis du nombre de couleurs utilisées
+pub fn parser_act(octets: &[u8]) -> Result<Vec<Rgb<u8>>, DitherError> {
+    if octets.len() != 768 && octets.len() != 772 {
+        return Err(erreur_fichier(format!(
+            "un fichier ACT fait 768 ou 772 octets, celui-ci en fait {}",
+            octets.len()
+        )));
+    }
+    let nombre = if octets.len() == 772 {
+        (u16::from_be_bytes([octets[768], octets[769]]) as usize).min(256)
+    } else {
+        256
+    };
+    Ok(octets[..nombre * 3]
+        .chunks_exact(3)
+        .map(|rgb| Rgb([rgb[0], rgb[1], rgb[2]]))
+        .collect())
+}
+
+/// Lecteur séquentiel big-endian pour le format ASE
+struct LecteurBinaire<'a> {
+    octets: &'a [u8],
+    position: usize,
+}
+
+impl<'a> LecteurBinaire<'a> {
+    fn lire(&mut self, taille: usize) -> Result<&'a [u8], DitherError> {
+        let fin = match self.position.checked_add(taille) {
+            Some(fin) if fin <= self.octets.len() => fin,
+            _ => return Err(erreur_fichier(format!("fin de fichier inattendue à l'octet {}", self.position))),
+        };
+        let tranche = &self.octets[self.position..fin];
+        self.position = fin;
+        Ok(tranche)
+    }
+
+    fn lire_u16(&mut self) -> Result<u16, DitherError> {
+        let octets = self.lire(2)?;
+        Ok(u16::from_be_bytes([octets[0], octets[1]]))
+    }
+
+    fn lire_u32(&mut self) -> Result<u32, DitherError> {
+        let octets = self.lire(4)?;
+        Ok(u32::from_be_bytes([octets[0], octets[1], octets[2], octets[3]]))
+    }
+
+    fn lire_f32(&mut self) -> Result<f32, DitherError> {
+        Ok(f32::from_bits(self.lire_u32()?))
+    }
+}
+
+/// Convertir une composante 0..1 en octet
+fn vers_octet(valeur: f32) -> u8 {
+    (valeur.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Palette Adobe Swatch Exchange : blocs de couleurs nommées en RGB, CMYK, LAB ou niveaux de gris
+pub fn parser_ase(octets: &[u8]) -> Result<Vec<Rgb<u8>>, DitherError> {
+    let mut lecteur = LecteurBinaire { octets, position: 0 };
+    if lecteur.lire(4)? != b"ASEF" {
+        return Err(erreur_fichier("signature 'ASEF' attendue".to_string()));
+    }
+    lecteur.lire(4)?; // version
+    let nombre_blocs = lecteur.lire_u32()?;
+
+    let mut couleurs = vec![];
+    for _ in 0..nombre_blocs {
+        let type_bloc = lecteur.lire_u16()?;
+        let longueur = lecteur.lire_u32()? as usize;
+        let debut = lecteur.position;
+        // Seuls les blocs de couleur (0x0001) nous intéressent, les groupes sont ignorés
+        if type_bloc == 0x0001 {
+            let longueur_nom = lecteur.lire_u16()? as usize;
+            lecteur.lire(longueur_nom * 2)?; // nom en UTF-16
+            let modele = lecteur.lire(4)?;
+            let couleur = match modele {
+                b"RGB " => Rgb([
+                    vers_octet(lecteur.lire_f32()?),
+                    vers_octet(lecteur.lire_f32()?),
+                    vers_octet(lecteur.lire_f32()?),
+                ]),
+                b"CMYK" => {
+                    let (c, m, y, k) =
+                        (lecteur.lire_f32()?, lecteur.lire_f32()?, lecteur.lire_f32()?, lecteur.lire_f32()?);
+                    Rgb([
+                        vers_octet((1.0 - c) * (1.0 - k)),
+                        vers_octet((1.0 - m) * (1.0 - k)),
+                        vers_octet((1.0 - y) * (1.0 - k)),
+                    ])
+                }
+                b"LAB " => {
+                    // L est stocké entre 0 et 1, a et b entre -128 et 127
+                    let l = lecteur.lire_f32()? * 100.0;
+                    espaces_couleur::lab_vers_srgb([l, lecteur.lire_f32()?, lecteur.lire_f32()?])
+                }
+                b"Gray" => {
+                    let gris = vers_octet(lecteur.lire_f32()?);
+                    Rgb([gris, gris, gris])
+                }
+                _ => {
+                    return Err(erreur_fichier(format!(
+                        "modèle de couleur inconnu '{}' à l'octet {}",
+                        String::from_utf8_lossy(modele),
+                        lecteur.position - 4
+                    )))
+                }
+            };
+            couleurs.push(couleur);
+        }
+        lecteur.position = debut;
+        lecteur.lire(longueur)?;
+    }
+    Ok(couleurs)
+}
+
+/// Sauvegarder une palette dans un fichier, le format étant déduit de l'extension :
+/// GIMP (`.gpl`), Adobe Color Table (`.act`), Paint.NET (`.txt`) ou JASC (`.pal`)
+pub fn sauvegarder_palette(chemin: &str, couleurs: &[Rgb<u8>]) -> Result<(), DitherError> {
+    let extension = extension_minuscule(chemin);
+
+    let contenu = match extension.as_str() {
+        "gpl" => {
+            let mut texte = String::from("GIMP Palette\nName: ditherpunk\n#\n");
+            for couleur in couleurs {
+                texte += &format!("{:3} {:3} {:3}\n", couleur[0], couleur[1], couleur[2]);
+            }
+            texte.into_bytes()
+        }
+        "pal" => {
+            let mut texte = format!("JASC-PAL\r\n0100\r\n{}\r\n", couleurs.len());
+            for couleur in couleurs {
+                texte += &format!("{} {} {}\r\n", couleur[0], couleur[1], couleur[2]);
+            }
+            texte.into_bytes()
+        }
+        "txt" => {
+            let mut texte = String::from("; Palette générée par ditherpunk\n");
+            for couleur in couleurs {
+                texte += &format!("FF{:02X}{:02X}{:02X}\n", couleur[0], couleur[1], couleur[2]);
+            }
+            texte.into_bytes()
+        }
+        "act" => {
+            if couleurs.len() > 256 {
+                return Err(erreur_fichier(format!(
+                    "un fichier ACT contient au plus 256 couleurs, la palette en a {}",
+                    couleurs.len()
+                )));
+            }
+            let mut octets = vec![0u8; 772];
+            for (i, couleur) in couleurs.iter().enumerate() {
+                octets[i * 3..i * 3 + 3].copy_from_slice(&couleur.0);
+            }
+            octets[768..770].copy_from_slice(&(couleurs.len() as u16).to_be_bytes());
+            octets[770..772].copy_from_slice(&0xFFFFu16.to_be_bytes()); // pas de couleur transparente
+            octets
+        }
+        _ => {
+            return Err(erreur_fichier(format!(
+                "extension de fichier de palette non reconnue : '{}'",
+                extension
+            )))
+        }
+    };
+
+    std::fs::write(chemin, contenu)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gpl_valide() {
+        let texte = "GIMP Palette\nName: test\nColumns: 2\n# commentaire\n\n  0   0   0 Noir\n255 128 1\n";
+        assert_eq!(parser_gpl(texte).unwrap(), vec![Rgb([0, 0, 0]), Rgb([255, 128, 1])]);
+    }
+
+    #[test]
+    fn gpl_mal_forme() {
+        assert!(matches!(parser_gpl("0 0 0\n"), Err(DitherError::FormatPalette { ligne: Some(1), .. })));
+        let composante_manquante = parser_gpl("GIMP Palette\n0 0\n").unwrap_err();
+        assert!(matches!(composante_manquante, DitherError::FormatPalette { ligne: Some(2), .. }));
+        let hors_limites = parser_gpl("GIMP Palette\n0 0 0\n0 256 0\n").unwrap_err();
+        assert!(matches!(hors_limites, DitherError::FormatPalette { ligne: Some(3), .. }));
+    }
+
+    #[test]
+    fn jasc_valide() {
+        let texte = "JASC-PAL\r\n0100\r\n2\r\n1 2 3\r\n4 5 6\r\n";
+        assert_eq!(parser_jasc_pal(texte).unwrap(), vec![Rgb([1, 2, 3]), Rgb([4, 5, 6])]);
+    }
+
+    #[test]
+    fn jasc_mal_forme() {
+        assert!(matches!(parser_jasc_pal("JASC-PAL\n0200\n0\n"), Err(DitherError::FormatPalette { ligne: Some(2), .. })));
+        assert!(matches!(parser_jasc_pal("JASC-PAL\n0100\n2\n1 2 3\n"), Err(DitherError::FormatPalette { ligne: None, .. })));
+        assert!(matches!(parser_jasc_pal("JASC-PAL\n0100\n1\n1 2 x\n"), Err(DitherError::FormatPalette { ligne: Some(4), .. })));
+    }
+
+    #[test]
+    fn paint_net_valide() {
+        let texte = "; Palette\nFF1D2B53\n80ffcc00\nabcdef\n";
+        let couleurs = parser_paint_net(texte).unwrap();
+        assert_eq!(couleurs, vec![Rgb([0x1d, 0x2b, 0x53]), Rgb([0xff, 0xcc, 0x00]), Rgb([0xab, 0xcd, 0xef])]);
+    }
+
+    #[test]
+    fn paint_net_mal_forme() {
+        assert!(matches!(parser_paint_net("FF12345\n"), Err(DitherError::FormatPalette { ligne: Some(1), .. })));
+        assert!(matches!(parser_paint_net("FFGG0000\n"), Err(DitherError::FormatPalette { ligne: Some(1), .. })));
+        assert!(matches!(parser_paint_net("+FF00000\n"), Err(DitherError::FormatPalette { ligne: Some(1), .. })));
+    }
+
+    #[test]
+    fn act_valide() {
+        let mut octets = vec![0u8; 768];
+        octets[..6].copy_from_slice(&[1, 2, 3, 4, 5, 6]);
+        assert_eq!(parser_act(&octets).unwrap().len(), 256);
+
+        // Avec le nombre de couleurs utilisées
+        octets.extend_from_slice(&[0, 2, 0xFF, 0xFF]);
+        assert_eq!(parser_act(&octets).unwrap(), vec![Rgb([1, 2, 3]), Rgb([4, 5, 6])]);
+    }
+
+    #[test]
+    fn act_mal_forme() {
+        assert!(matches!(parser_act(&[0u8; 100]), Err(DitherError::FormatPalette { ligne: None, .. })));
+    }
+
+    #[test]
+    fn paint_net_refuse_une_ligne_non_ascii_sans_paniquer() {
+        let erreur = parser_paint_net("FF000000\naé34567\n").unwrap_err();
+        assert!(matches!(erreur, DitherError::FormatPalette { ligne: Some(2), .. }));
+    }
+
+    /// Construire un bloc de couleur ASE nommé "a"
+    fn bloc_ase(modele: &[u8; 4], valeurs: &[f32]) -> Vec<u8> {
+        let mut contenu = vec![0, 2, 0, b'a', 0, 0];
+        contenu.extend_from_slice(modele);
+        for valeur in valeurs {
+            contenu.extend_from_slice(&valeur.to_be_bytes());
+        }
+        contenu.extend_from_slice(&2u16.to_be_bytes()); // couleur normale
+        let mut bloc = 0x0001u16.to_be_bytes().to_vec();
+        bloc.extend_from_slice(&(contenu.len() as u32).to_be_bytes());
+        bloc.extend(contenu);
+        bloc
+    }
+
+    /// Assembler un fichier ASE à partir de ses blocs
+    fn fichier_ase(blocs: &[Vec<u8>]) -> Vec<u8> {
+        let mut octets = b"ASEF\x00\x01\x00\x00".to_vec();
+        octets.extend_from_slice(&(blocs.len() as u32).to_be_bytes());
+        for bloc in blocs {
+            octets.extend_from_slice(bloc);
+        }
+        octets
+    }
+
+    #[test]
+    fn ase_valide() {
+        // Début et fin de groupe, ignorés
+        let debut_groupe = vec![0xC0, 0x01, 0, 0, 0, 4, 0, 1, 0, 0];
+        let fin_groupe = vec![0xC0, 0x02, 0, 0, 0, 0];
+        let octets = fichier_ase(&[
+            debut_groupe,
+            bloc_ase(b"RGB ", &[1.0, 0.5, 0.0]),
+            bloc_ase(b"CMYK", &[0.0, 1.0, 1.0, 0.0]),
+            bloc_ase(b"LAB ", &[1.0, 0.0, 0.0]),
+            bloc_ase(b"Gray", &[0.25]),
+            fin_groupe,
+        ]);
+        let couleurs = parser_ase(&octets).unwrap();
+        assert_eq!(
+            couleurs,
+            vec![Rgb([255, 128, 0]), Rgb([255, 0, 0]), Rgb([255, 255, 255]), Rgb([64, 64, 64])]
+        );
+    }
+
+    #[test]
+    fn ase_mal_forme() {
+        let complet = fichier_ase(&[bloc_ase(b"RGB ", &[1.0, 0.5, 0.0])]);
+        // Chaque troncature doit donner une erreur, jamais une panique
+        for longueur in 0..complet.len() {
+            assert!(
+                matches!(parser_ase(&complet[..longueur]), Err(DitherError::FormatPalette { ligne: None, .. })),
+                "fichier tronqué à {} octets accepté",
+                longueur
+            );
+        }
+
+        let mut signature = complet.clone();
+        signature[0] = b'X';
+        assert!(parser_ase(&signature).is_err());
+
+        let modele_inconnu = fichier_ase(&[bloc_ase(b"HSV ", &[0.0, 0.0, 0.0])]);
+        assert!(parser_ase(&modele_inconnu).is_err());
+
+        // Longueur de bloc démesurée et nombre de blocs annoncé supérieur au contenu
+        let mut longueur_bloc = complet.clone();
+        longueur_bloc[14..18].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(parser_ase(&longueur_bloc).is_err());
+        let mut nombre_blocs = complet;
+        nombre_blocs[8..12].copy_from_slice(&1000u32.to_be_bytes());
+        assert!(parser_ase(&nombre_blocs).is_err());
+    }
+}
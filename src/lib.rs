@@ -0,0 +1,36 @@
+//! Ditherpunk : transformation d'images en versions monochromes ou réduites à une palette de couleurs.
+//!
+//! Chaque algorithme implémente le trait [`Ditherer`] et peut être utilisé directement
+//! depuis une autre application, sans passer par l'exécutable.
+
+pub mod algorithmes;
+pub mod bruit_bleu;
+pub mod couleurs;
+pub mod diffusion_points;
+pub mod diffusion_variable;
+pub mod erreur;
+pub mod espaces_couleur;
+pub mod format_palette;
+pub mod melange;
+pub mod metrique;
+pub mod palette;
+pub mod palettes_retro;
+pub mod quantification;
+pub mod recherche_directe;
+pub mod trame;
+pub mod utils;
+
+use image::RgbImage;
+
+pub use algorithmes::{
+    DiffusionErreur, DiffusionPoints, DiffusionVariable, MatriceDiffusionErreur, PlusProcheCouleur,
+    RechercheBinaireDirecte, Riemersma, Seuillage, Similigravure, SimiligravureCmjn, TramageAleatoire, TramageOrdonne,
+};
+pub use erreur::DitherError;
+pub use palette::Palette;
+
+/// Algorithme de tramage réduisant une image RGB8 aux couleurs d'une palette
+pub trait Ditherer {
+    /// Produire une nouvelle image tramée à partir de l'image source et de la palette
+    fn dither(&self, img: &RgbImage, palette: &Palette) -> Result<RgbImage, DitherError>;
+}
@@ -1,23 +1,34 @@
-mod utils;
+use std::env;
+use std::path::{Path, PathBuf};
 use std::str;
 use std::str::FromStr;
 
 use argh::FromArgs;
-//use image::io::Reader as ImageReader;
-//use image::DynamicImage;
-//use image::{Rgb, RgbImage};
+use ditherpunk::algorithmes::{ChoixSeuil, NOMS_PLAQUES_CMJN, ORDRE_BAYER_MAX, PASSES_DBS_PAR_DEFAUT};
+use ditherpunk::bruit_bleu::{self, TAILLE_BRUIT_BLEU_MAX};
+use ditherpunk::diffusion_points::ClassesDiffusionPoints;
+use ditherpunk::diffusion_variable::CoefficientsVariables;
+use ditherpunk::espaces_couleur::EspaceCouleur;
+use ditherpunk::melange::MelangePalette;
+use ditherpunk::metrique::MetriqueCouleur;
+use ditherpunk::quantification::Quantificateur;
+use ditherpunk::recherche_directe::EtatInitialDbs;
+use ditherpunk::trame::{FonctionPoint, Trame};
+use ditherpunk::{couleurs, format_palette, palettes_retro, utils};
+use image::{Rgb, RgbImage};
+use ditherpunk::{
+    DiffusionErreur, DiffusionPoints, DiffusionVariable, DitherError, Ditherer, MatriceDiffusionErreur, Palette,
+    PlusProcheCouleur, RechercheBinaireDirecte, Riemersma, Seuillage, Similigravure, SimiligravureCmjn, TramageAleatoire,
+    TramageOrdonne,
+};
 
 #[derive(Debug, Clone, PartialEq, FromArgs)]
 /// Convertit une image en monochrome ou vers une palette réduite de couleurs.
 struct DitherArgs {
 
-    /// le fichier d’entrée
+    /// le fichier d’entrée puis le fichier de sortie (optionnel), inutiles pour la commande palettes
     #[argh(positional)]
-    input: String,
-
-    /// le fichier de sortie (optionnel)
-    #[argh(positional)]
-    output: Option<String>,
+    fichiers: Vec<String>,
 
     /// le mode d’opération
     #[argh(subcommand)]
@@ -28,22 +39,83 @@ struct DitherArgs {
 #[argh(subcommand)]
 enum Mode {
     Seuil(OptsSeuil),
+    Halftone(OptsHalftone),
     Palette(OptsPalette),
     Dithering(OptsDithering),
     DiffussionErreur(OptsDiffusionErreur),
+    Dbs(OptsDbs),
+    Palettes(OptsPalettes),
 }
 
 #[derive(Debug, Clone, PartialEq, FromArgs)]
 #[argh(subcommand, name="seuil")]
 /// Rendu de l’image par seuillage monochrome.
 struct OptsSeuil {
-    /// la couleur 1 personnalisée (optionnelle)
+    /// la couleur 1 personnalisée (optionnelle) : nom, #rrggbb, #rgb ou r,g,b
     #[argh(option)]
     couleur_1: Option<String>,
 
-    /// la couleur 2 personnalisée (optionnelle)
+    /// la couleur 2 personnalisée (optionnelle) : nom, #rrggbb, #rgb ou r,g,b
     #[argh(option)]
     couleur_2: Option<String>,
+
+    /// le seuil de luminosité : une valeur de 0 à 255 (128 par défaut), auto (méthode d’Otsu), moyenne ou mediane
+    #[argh(option, default = "ChoixSeuil::default()")]
+    seuil: ChoixSeuil,
+}
+
+#[derive(Debug, Clone, PartialEq, FromArgs)]
+#[argh(subcommand, name = "halftone")]
+/// Rendu de l’image par une trame d’impression à points groupés.
+struct OptsHalftone {
+    /// la forme des points : rond (par défaut), elliptique, ligne, carre ou losange
+    #[argh(option, default = "FonctionPoint::Rond")]
+    point: FonctionPoint,
+    /// la linéature de la trame en lignes par pouce (30 par défaut)
+    #[argh(option, default = "30.0", from_str_fn(parser_strictement_positif))]
+    lpi: f32,
+    /// la résolution de l’image en pixels par pouce (300 par défaut)
+    #[argh(option, default = "300.0", from_str_fn(parser_strictement_positif))]
+    dpi: f32,
+    /// l’angle de la trame en degrés (45 par défaut)
+    #[argh(option, default = "45.0")]
+    angle: f32,
+    /// la couleur du papier (blanc par défaut) : nom, #rrggbb, #rgb ou r,g,b
+    #[argh(option)]
+    couleur_1: Option<String>,
+    /// la couleur de l’encre (noir par défaut) : nom, #rrggbb, #rgb ou r,g,b
+    #[argh(option)]
+    couleur_2: Option<String>,
+    /// le fichier d’une palette dont la couleur la plus claire sert de papier et la plus sombre d’encre
+    #[argh(option)]
+    palette_file: Option<String>,
+    /// le nom d’une palette prédéfinie dont la couleur la plus claire sert de papier et la plus sombre d’encre
+    #[argh(option)]
+    palette: Option<String>,
+    /// générer automatiquement une palette de N couleurs adaptée à l’image, pour le papier et l’encre
+    #[argh(option)]
+    auto_palette: Option<usize>,
+    /// l’algorithme de génération de la palette automatique : median-cut (par défaut), kmeans, wu ou octree
+    #[argh(option, default = "Quantificateur::MedianCut")]
+    quantizer: Quantificateur,
+    /// l’espace de couleur de la quantification : rgb (par défaut), lineaire, lab ou oklab
+    #[argh(option, default = "EspaceCouleur::Srgb")]
+    espace: EspaceCouleur,
+    /// la graine aléatoire des k-moyennes (0 par défaut)
+    #[argh(option, default = "0")]
+    graine: u64,
+    /// séparer l’image en quadrichromie (cyan 15°, magenta 75°, jaune 0°, noir 45°), --angle est alors ignoré
+    #[argh(switch)]
+    cmjn: bool,
+    /// la part du gris remplacée par de l’encre noire en quadrichromie, de 0 à 1 (1 par défaut)
+    #[argh(option, default = "1.0", from_str_fn(parser_fraction))]
+    gcr: f32,
+    /// la part de ce noir retirée des encres colorées, de 0 à 1 (1 par défaut)
+    #[argh(option, default = "1.0", from_str_fn(parser_fraction))]
+    ucr: f32,
+    /// écrire chaque plaque de la quadrichromie dans un fichier séparé (suffixes _cyan, _magenta, _jaune, _noir ; 1 bit en .pbm)
+    #[argh(switch)]
+    plaques: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, FromArgs)]
@@ -53,7 +125,36 @@ struct OptsPalette {
 
     /// le nombre de couleurs à utiliser, dans la liste [NOIR, BLANC, ROUGE, VERT, BLEU, JAUNE, CYAN, MAGENTA]
     #[argh(option)]
-    n_couleurs: usize
+    n_couleurs: Option<usize>,
+
+    /// les couleurs de la palette, séparées par des virgules ou en répétant l'option (ex : noir,rouge,#ffcc00)
+    #[argh(option)]
+    couleurs: Vec<String>,
+
+    /// le fichier de palette à utiliser (.gpl, .act, .ase, .txt Paint.NET ou .pal JASC)
+    #[argh(option)]
+    palette_file: Option<String>,
+    /// le nom d'une palette prédéfinie (ex : gameboy, pico-8, c64), voir la commande palettes
+    #[argh(option)]
+    palette: Option<String>,
+    /// générer automatiquement une palette de N couleurs adaptée à l'image
+    #[argh(option)]
+    auto_palette: Option<usize>,
+    /// l'algorithme de génération de la palette automatique : median-cut (par défaut), kmeans, wu ou octree
+    #[argh(option, default = "Quantificateur::MedianCut")]
+    quantizer: Quantificateur,
+    /// l'espace de couleur de la quantification : rgb (par défaut), lineaire, lab ou oklab
+    #[argh(option, default = "EspaceCouleur::Srgb")]
+    espace: EspaceCouleur,
+    /// la graine aléatoire des k-moyennes (0 par défaut)
+    #[argh(option, default = "0")]
+    graine: u64,
+    /// exporter la palette utilisée dans un fichier (.gpl, .act, .txt ou .pal)
+    #[argh(option)]
+    exporter_palette: Option<String>,
+    /// la métrique de recherche de la couleur la plus proche : euclidienne (par défaut), redmean, deltae76, ciede2000 ou oklab
+    #[argh(option, default = "MetriqueCouleur::Euclidienne")]
+    metrique: MetriqueCouleur,
 }
 
 #[derive(Debug, Clone, PartialEq, FromArgs)]
@@ -62,39 +163,91 @@ struct OptsPalette {
 struct OptsDiffusionErreur {
     /// le nombre de couleurs à utiliser, dans la liste [NOIR, BLANC, ROUGE, VERT, BLEU, JAUNE, CYAN, MAGENTA]
     #[argh(option)]
-    n_couleurs: usize,
-    /// la matrice de diffusion d’erreur à utiliser
+    n_couleurs: Option<usize>,
+    /// les couleurs de la palette, séparées par des virgules ou en répétant l'option (ex : noir,rouge,#ffcc00)
+    #[argh(option)]
+    couleurs: Vec<String>,
+    /// le fichier de palette à utiliser (.gpl, .act, .ase, .txt Paint.NET ou .pal JASC)
+    #[argh(option)]
+    palette_file: Option<String>,
+    /// le nom d'une palette prédéfinie (ex : gameboy, pico-8, c64), voir la commande palettes
+    #[argh(option)]
+    palette: Option<String>,
+    /// générer automatiquement une palette de N couleurs adaptée à l'image
+    #[argh(option)]
+    auto_palette: Option<usize>,
+    /// l'algorithme de génération de la palette automatique : median-cut (par défaut), kmeans, wu ou octree
+    #[argh(option, default = "Quantificateur::MedianCut")]
+    quantizer: Quantificateur,
+    /// l'espace de couleur de la quantification : rgb (par défaut), lineaire, lab ou oklab
+    #[argh(option, default = "EspaceCouleur::Srgb")]
+    espace: EspaceCouleur,
+    /// la graine aléatoire des k-moyennes (0 par défaut)
+    #[argh(option, default = "0")]
+    graine: u64,
+    /// exporter la palette utilisée dans un fichier (.gpl, .act, .txt ou .pal)
     #[argh(option)]
-    matrice: MatriceDiffusionErreur,
+    exporter_palette: Option<String>,
+    /// la métrique de recherche de la couleur la plus proche : euclidienne (par défaut), redmean, deltae76, ciede2000 ou oklab
+    #[argh(option, default = "MetriqueCouleur::Euclidienne")]
+    metrique: MetriqueCouleur,
+    /// la matrice de diffusion d’erreur à utiliser (floydsteinberg par défaut), voir --liste-matrices
+    #[argh(option)]
+    matrice: Option<MatriceDiffusionErreur>,
+    /// le noyau de --matrice custom, lignes séparées par ';' et '*' pour le pixel courant (ex : "0 * 7; 3 5 1")
+    #[argh(option)]
+    noyau: Option<String>,
+    /// un fichier contenant le noyau de --matrice custom, une ligne du noyau par ligne du fichier
+    #[argh(option)]
+    noyau_fichier: Option<String>,
+    /// le diviseur appliqué aux poids du noyau personnalisé (1 par défaut)
+    #[argh(option)]
+    diviseur: Option<f32>,
+    /// afficher les matrices de diffusion d’erreur disponibles avec leurs poids
+    #[argh(switch)]
+    liste_matrices: bool,
+    /// la méthode de diffusion : matrice (parcours ligne par ligne, par défaut), riemersma (courbe de Hilbert, sans matrice), points (diffusion par points de Knuth), ostromoukhov ou zhou-fang (coefficients variables selon le niveau de gris)
+    #[argh(option, default = "MethodeDiffusion::Matrice")]
+    methode: MethodeDiffusion,
+    /// la matrice de classes de --methode points : knuth (par défaut) ou knuth-retouchee (matrice de Knuth retouchée par une recherche heuristique maison)
+    #[argh(option, default = "ClassesDiffusionPoints::Knuth")]
+    classes: ClassesDiffusionPoints,
+    /// diffuser l'erreur en lumière linéaire (décodage gamma sRGB) pour conserver la luminosité perçue
+    #[argh(switch)]
+    lineaire: bool,
+    /// parcourir les lignes en alternant le sens (boustrophédon) pour éviter les artefacts directionnels
+    #[argh(switch)]
+    serpentin: bool,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum MatriceDiffusionErreur {
-    Simple2D,
-    FloydSteinberg,
-    JarvisJudiceNinke,
-    Atkinson,
+#[derive(Debug, Clone, PartialEq, FromArgs)]
+#[argh(subcommand, name = "dbs")]
+/// Rendu monochrome par recherche binaire directe, lent mais de grande qualité.
+struct OptsDbs {
+    /// le tramage de départ : aleatoire, bayer ou floyd-steinberg (par défaut)
+    #[argh(option, default = "EtatInitialDbs::FloydSteinberg")]
+    initial: EtatInitialDbs,
+    /// le nombre maximal de passes sur l’image (10 par défaut)
+    #[argh(option, default = "PASSES_DBS_PAR_DEFAUT")]
+    passes: usize,
+    /// la couleur des pixels clairs (blanc par défaut) : nom, #rrggbb, #rgb ou r,g,b
+    #[argh(option)]
+    couleur_1: Option<String>,
+    /// la couleur des pixels sombres (noir par défaut) : nom, #rrggbb, #rgb ou r,g,b
+    #[argh(option)]
+    couleur_2: Option<String>,
 }
 
-// Implémentation de FromStr pour Enum
-impl FromStr for MatriceDiffusionErreur {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "simple2d" => Ok(MatriceDiffusionErreur::Simple2D),
-            "floydsteinberg" => Ok(MatriceDiffusionErreur::FloydSteinberg),
-            "jarvisjudiceninke" => Ok(MatriceDiffusionErreur::JarvisJudiceNinke),
-            "atkinson" => Ok(MatriceDiffusionErreur::Atkinson),
-            _ => Err(format!("Matrice de diffusion d'erreur invalide: {}", s)),
-        }
-    }
-}
+#[derive(Debug, Clone, PartialEq, FromArgs)]
+#[argh(subcommand, name = "palettes")]
+/// Liste les palettes prédéfinies avec leur nuancier.
+struct OptsPalettes {}
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Methode {
     Aleatoire,
     Ordonne,
+    BruitBleu,
 }
 
 // Implémentation de FromStr pour Enum
@@ -105,6 +258,7 @@ impl FromStr for Methode {
         match s.to_lowercase().as_str() {
             "aleatoire" => Ok(Methode::Aleatoire),
             "ordonne" => Ok(Methode::Ordonne),
+            "bruit-bleu" | "bruitbleu" => Ok(Methode::BruitBleu),
             _ => Err(format!("Méthode de dithering invalide: {}", s)),
         }
     }
@@ -112,126 +266,584 @@ impl FromStr for Methode {
 
 
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum MethodeDiffusion {
+    Matrice,
+    Riemersma,
+    Points,
+    Ostromoukhov,
+    ZhouFang,
+}
+
+// Implémentation de FromStr pour Enum
+impl FromStr for MethodeDiffusion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "matrice" => Ok(MethodeDiffusion::Matrice),
+            "riemersma" => Ok(MethodeDiffusion::Riemersma),
+            "points" | "knuth" => Ok(MethodeDiffusion::Points),
+            "ostromoukhov" => Ok(MethodeDiffusion::Ostromoukhov),
+            "zhou-fang" | "zhoufang" => Ok(MethodeDiffusion::ZhouFang),
+            _ => Err(format!("Méthode de diffusion invalide: {}", s)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, FromArgs)]
 #[argh(subcommand, name = "dithering")]
 /// Rendu de l'image par dithering.
 pub struct OptsDithering {
-    /// la méthode de tramage à utiliser
+    /// la méthode de tramage à utiliser : aleatoire, ordonne (Bayer) ou bruit-bleu (void-and-cluster)
     #[argh(option)]
     tramage: Methode,
+    /// l'ordre de la matrice de Bayer du tramage ordonné, de 0 à 6 (matrice de 2^N x 2^N, 2 par défaut)
+    #[argh(option, default = "2", from_str_fn(parser_ordre_bayer))]
+    ordre: u32,
+    /// la taille de la matrice de bruit bleu, de 4 à 256 (64 par défaut)
+    #[argh(option, default = "64", from_str_fn(parser_taille_bruit_bleu))]
+    taille_bruit: usize,
+    /// la graine aléatoire de la matrice de bruit bleu et des k-moyennes (0 par défaut)
+    #[argh(option, default = "0")]
+    graine: u64,
+    /// une image en niveaux de gris répétée sur l'image comme carte de seuils du tramage ordonné
+    #[argh(option)]
+    carte_seuil: Option<String>,
+    /// le fichier de palette à utiliser (.gpl, .act, .ase, .txt Paint.NET ou .pal JASC)
+    #[argh(option)]
+    palette_file: Option<String>,
+    /// le nom d'une palette prédéfinie (ex : gameboy, pico-8, c64), voir la commande palettes
+    #[argh(option)]
+    palette: Option<String>,
+    /// générer automatiquement une palette de N couleurs adaptée à l'image
+    #[argh(option)]
+    auto_palette: Option<usize>,
+    /// l'algorithme de génération de la palette automatique : median-cut (par défaut), kmeans, wu ou octree
+    #[argh(option, default = "Quantificateur::MedianCut")]
+    quantizer: Quantificateur,
+    /// l'espace de couleur de la quantification : rgb (par défaut), lineaire, lab ou oklab
+    #[argh(option, default = "EspaceCouleur::Srgb")]
+    espace: EspaceCouleur,
+    /// le mélange des couleurs de la palette : decalage (par défaut au-delà de 2 couleurs) ou yliluoma
+    #[argh(option)]
+    melange: Option<MelangePalette>,
+    /// l'écart de seuil du mélange par décalage (calculé d'après la palette par défaut)
+    #[argh(option)]
+    ecart: Option<f32>,
+}
+
+/// Lire l'ordre de la matrice de Bayer en refusant les matrices démesurées
+fn parser_ordre_bayer(valeur: &str) -> Result<u32, String> {
+    let ordre: u32 = valeur.parse().map_err(|_| format!("Ordre de matrice de Bayer invalide: {}", valeur))?;
+    if ordre > ORDRE_BAYER_MAX {
+        return Err(format!("L'ordre de la matrice de Bayer doit être compris entre 0 et {}", ORDRE_BAYER_MAX));
+    }
+    Ok(ordre)
+}
+
+/// Lire la taille de la matrice de bruit bleu en refusant les générations trop longues
+fn parser_taille_bruit_bleu(valeur: &str) -> Result<usize, String> {
+    let taille: usize = valeur.parse().map_err(|_| format!("Taille de matrice de bruit bleu invalide: {}", valeur))?;
+    if !(4..=TAILLE_BRUIT_BLEU_MAX).contains(&taille) {
+        return Err(format!("La taille de la matrice de bruit bleu doit être comprise entre 4 et {}", TAILLE_BRUIT_BLEU_MAX));
+    }
+    Ok(taille)
+}
+
+/// Dossier où sont conservées les matrices de bruit bleu déjà générées
+fn dossier_cache() -> PathBuf {
+    if let Some(dossier) = env::var_os("XDG_CACHE_HOME").filter(|d| !d.is_empty()) {
+        return PathBuf::from(dossier).join("ditherpunk");
+    }
+    match env::var_os("HOME").filter(|d| !d.is_empty()) {
+        Some(maison) => PathBuf::from(maison).join(".cache").join("ditherpunk"),
+        None => env::temp_dir().join("ditherpunk"),
+    }
+}
+
+/// Lire un nombre strictement positif (linéature, résolution...)
+fn parser_strictement_positif(valeur: &str) -> Result<f32, String> {
+    match valeur.parse::<f32>() {
+        Ok(nombre) if nombre.is_finite() && nombre > 0.0 => Ok(nombre),
+        _ => Err(format!("Un nombre strictement positif est attendu: {}", valeur)),
+    }
+}
+
+/// Lire une proportion comprise entre 0 et 1
+fn parser_fraction(valeur: &str) -> Result<f32, String> {
+    match valeur.parse::<f32>() {
+        Ok(nombre) if (0.0..=1.0).contains(&nombre) => Ok(nombre),
+        _ => Err(format!("Un nombre entre 0 et 1 est attendu: {}", valeur)),
+    }
 }
 
+/// Chemin du fichier d'une plaque : le nom de la plaque est ajouté avant l'extension
+fn chemin_plaque(path_out: &str, plaque: &str) -> String {
+    let chemin = Path::new(path_out);
+    let nom = chemin.file_stem().and_then(|n| n.to_str()).unwrap_or("out");
+    let nom_plaque = match chemin.extension().and_then(|e| e.to_str()) {
+        Some(extension) => format!("{}_{}.{}", nom, plaque, extension),
+        None => format!("{}_{}", nom, plaque),
+    };
+    chemin.with_file_name(nom_plaque).to_string_lossy().into_owned()
+}
 
 fn main() {
     let args: DitherArgs = argh::from_env();
-    let path_in = args.input;
+    if let Err(err) = executer(args) {
+        eprintln!("Erreur : {}", err);
+        std::process::exit(code_sortie(&err)); // Quitte le programme avec un code d'erreur
+    }
+}
+
+/// Associer à chaque erreur un code de sortie distinct
+fn code_sortie(err: &DitherError) -> i32 {
+    match err {
+        DitherError::Arguments(_) => 1,
+        DitherError::Io(_) => 2,
+        DitherError::Decodage(_) => 3,
+        DitherError::Encodage(_) => 4,
+        DitherError::CouleurInconnue(_) => 5,
+        DitherError::HorsLimites { .. } => 6,
+        DitherError::PaletteInvalide(_) => 7,
+        DitherError::FormatPalette { .. } => 8,
+        DitherError::NoyauInvalide(_) => 9,
+        DitherError::MatriceInvalide(_) => 10,
+    }
+}
+
+/// Exécuter le traitement demandé sur la ligne de commande
+fn executer(args: DitherArgs) -> Result<(), DitherError> {
+    if let Mode::Palettes(_) = args.mode {
+        for palette in palettes_retro::PALETTES_RETRO {
+            let couleurs = palette.couleurs();
+            println!("{} - {} ({} couleurs)", palette.nom, palette.description, couleurs.len());
+            palettes_retro::afficher_nuancier(&couleurs);
+        }
+        return Ok(());
+    }
+    if let Mode::DiffussionErreur(OptsDiffusionErreur { liste_matrices: true, .. }) = args.mode {
+        for matrice in MatriceDiffusionErreur::TOUTES {
+            println!("{} - {}", matrice.nom(), matrice.description());
+            for ligne in matrice.poids().lines() {
+                println!("  {}", ligne);
+            }
+            println!("  (÷ {})", matrice.diviseur());
+        }
+        return Ok(());
+    }
+
+    let (path_in, output) = match args.fichiers.as_slice() {
+        [input] => (input.clone(), None),
+        [input, output] => (input.clone(), Some(output.clone())),
+        _ => {
+            return Err(DitherError::Arguments(
+                "un fichier d'entrée et éventuellement un fichier de sortie sont attendus".to_string(),
+            ))
+        }
+    };
     println!("path_in: {}", path_in);
-    let path_out = args.output.unwrap_or("output/out.png".to_string());
+    let path_out = output.unwrap_or("output/out.png".to_string());
     println!("path_out: {}", path_out);
     
-    let mut image_rgb8 = utils::charger_image_rgb8(&path_in); // Question 2
+    let image_rgb8 = utils::charger_image_rgb8(&path_in)?; // Question 2
 
-    let pixel = utils::recuperer_pixel(&image_rgb8, 32, 52); // Question 4
-    println!("La couleur du pixel (32, 52) est : {:?}", pixel);
+    // Affichage indicatif : une image trop petite pour contenir le pixel (32, 52) reste traitée
+    if let Ok(pixel) = utils::recuperer_pixel(&image_rgb8, 32, 52) { // Question 4
+        println!("La couleur du pixel (32, 52) est : {:?}", pixel);
 
-    //utils::transformer_pixels_un_sur_deux(&mut image_rgb8); // Question 5
+        //utils::transformer_pixels_un_sur_deux(&mut image_rgb8); // Question 5
 
-    let luminosite_pixel = utils::luminosite_pixel(&pixel); // Question 6
-    println!("La luminosité du pixel (32, 52) est : {:?}", luminosite_pixel);
+        let luminosite_pixel = utils::luminosite_pixel(&pixel); // Question 6
+        println!("La luminosité du pixel (32, 52) est : {:?}", luminosite_pixel);
+    }
 
     //utils::monochrome_par_seuillage(&mut image_rgb8); // Question 7
 
-    match &args.mode {
+    let (ditherer, palette): (Box<dyn Ditherer>, Palette) = match &args.mode {
         Mode::Seuil(opts_seuil) => {
-            let couleurs = utils::creer_liste_couleurs();
-            let couleur_1_rgb = if let Some(couleur) = &opts_seuil.couleur_1 {
-                utils::obtenir_couleur_par_nom(couleur, &couleurs)
-            }
-            else {
-                utils::obtenir_couleur_par_nom("blanc", &couleurs) // valeur par défaut
-            };
+            let couleur_1_rgb = couleurs::parser_couleur(opts_seuil.couleur_1.as_deref().unwrap_or("blanc"))?; // blanc par défaut
             println!("La couleur 1 est : {:?}", couleur_1_rgb);
 
-            let couleur_2_rgb = if let Some(couleur) = &opts_seuil.couleur_2 {
-                utils::obtenir_couleur_par_nom(couleur, &couleurs)
-            }
-            else {
-                utils::obtenir_couleur_par_nom("noir", &couleurs) // valeur par défaut
-            };
+            let couleur_2_rgb = couleurs::parser_couleur(opts_seuil.couleur_2.as_deref().unwrap_or("noir"))?; // noir par défaut
             println!("La couleur 2 est : {:?}", couleur_2_rgb);
 
-            utils::monochrome_par_seuillage(&mut image_rgb8, couleur_1_rgb, couleur_2_rgb); // Question 8
-        },
-        Mode::Palette(opts_palette) => {
-            println!("Mode palette avec {} couleurs", opts_palette.n_couleurs);
+            let seuil = opts_seuil.seuil.calculer(&image_rgb8);
+            println!("Le seuil ({:?}) est : {}", opts_seuil.seuil, seuil);
 
-            let couleurs = utils::creer_liste_couleurs();
-
-            if opts_palette.n_couleurs > couleurs.len() {
-                eprintln!(
-                    "Erreur : Le nombre de couleurs demandé ({}) dépasse le nombre total de couleurs disponibles ({}).",
-                    opts_palette.n_couleurs,
-                    couleurs.len()
-                );
-                std::process::exit(1);
+            // Le seuil déjà calculé est transmis tel quel pour ne pas refaire l'histogramme
+            (Box::new(Seuillage { seuil: ChoixSeuil::Valeur(seuil) }), Palette::new(vec![couleur_1_rgb, couleur_2_rgb])) // Question 8
+        },
+        Mode::Halftone(opts_halftone) if opts_halftone.cmjn => {
+            if opts_halftone.palette_file.is_some() || opts_halftone.palette.is_some() || opts_halftone.auto_palette.is_some() {
+                eprintln!("Attention : la palette est ignorée en quadrichromie");
             }
-
-            let mut couleurs_palette = vec![];
-            for i in 0..opts_palette.n_couleurs {
-                couleurs_palette.push(couleurs[i].1);
+            println!(
+                "Quadrichromie {:?} de {} lpi à {} dpi (GCR {}, UCR {})",
+                opts_halftone.point, opts_halftone.lpi, opts_halftone.dpi, opts_halftone.gcr, opts_halftone.ucr
+            );
+            let similigravure = SimiligravureCmjn::new(
+                opts_halftone.point,
+                opts_halftone.lpi,
+                opts_halftone.dpi,
+                opts_halftone.gcr,
+                opts_halftone.ucr,
+            );
+            if opts_halftone.plaques {
+                for (plaque, nom) in similigravure.plaques(&image_rgb8).iter().zip(NOMS_PLAQUES_CMJN) {
+                    let chemin = chemin_plaque(&path_out, nom);
+                    utils::sauvegarder_image_gris(plaque, &chemin)?;
+                    println!("Plaque {} sauvegardée avec succès à l'emplacement : {}", nom, chemin);
+                }
+                return Ok(());
             }
+            (Box::new(similigravure), Palette::new(Vec::new()))
+        },
+        Mode::Halftone(opts_halftone) => {
+            let couleurs_palette = SourcesPalette {
+                palette_file: opts_halftone.palette_file.as_deref(),
+                palette: opts_halftone.palette.as_deref(),
+                auto_palette: opts_halftone.auto_palette,
+                quantificateur: opts_halftone.quantizer,
+                espace: opts_halftone.espace,
+                graine: opts_halftone.graine,
+                ..Default::default()
+            }
+            .construire(&image_rgb8)?;
+            let (papier, encre) = match couleurs_palette {
+                Some(_) if opts_halftone.couleur_1.is_some() || opts_halftone.couleur_2.is_some() => {
+                    return Err(DitherError::Arguments(
+                        "--couleur-1 et --couleur-2 sont incompatibles avec une palette".to_string(),
+                    ))
+                }
+                Some(couleurs_palette) => papier_et_encre(&couleurs_palette)?,
+                None => (
+                    couleurs::parser_couleur(opts_halftone.couleur_1.as_deref().unwrap_or("blanc"))?,
+                    couleurs::parser_couleur(opts_halftone.couleur_2.as_deref().unwrap_or("noir"))?,
+                ),
+            };
+            println!("Papier : {:?}, encre : {:?}", papier, encre);
+            println!(
+                "Trame {:?} de {} lpi à {} dpi, inclinée à {}°",
+                opts_halftone.point, opts_halftone.lpi, opts_halftone.dpi, opts_halftone.angle
+            );
+            let trame = Trame::new(opts_halftone.point, opts_halftone.lpi, opts_halftone.dpi, opts_halftone.angle);
+            (Box::new(Similigravure { trame }), Palette::new(vec![papier, encre]))
+        },
+        Mode::Palette(opts_palette) => {
+            let couleurs_palette = SourcesPalette {
+                n_couleurs: opts_palette.n_couleurs,
+                couleurs: &opts_palette.couleurs,
+                palette_file: opts_palette.palette_file.as_deref(),
+                palette: opts_palette.palette.as_deref(),
+                auto_palette: opts_palette.auto_palette,
+                quantificateur: opts_palette.quantizer,
+                espace: opts_palette.espace,
+                graine: opts_palette.graine,
+            }
+            .construire_obligatoire(&image_rgb8)?;
+            println!("Mode palette avec {} couleurs", couleurs_palette.len());
             println!("Les couleurs de la palette sont : {:?}", couleurs_palette);
+            if let Some(chemin) = &opts_palette.exporter_palette {
+                format_palette::sauvegarder_palette(chemin, &couleurs_palette)?;
+                println!("Palette exportée à l'emplacement : {}", chemin);
+            }
 
-            utils::monochrome_par_palette(&mut image_rgb8, couleurs_palette); // Question 10
+            (Box::new(PlusProcheCouleur), Palette::new(couleurs_palette).avec_metrique(opts_palette.metrique)) // Question 10
         },
-        Mode::Dithering(_opts_dithering) => {
-           
+        Mode::Dithering(opts_dithering) => {
             println!("Mode dithering");
-            match _opts_dithering.tramage {
+            let couleurs_palette = SourcesPalette {
+                n_couleurs: None,
+                couleurs: &[],
+                palette_file: opts_dithering.palette_file.as_deref(),
+                palette: opts_dithering.palette.as_deref(),
+                auto_palette: opts_dithering.auto_palette,
+                quantificateur: opts_dithering.quantizer,
+                espace: opts_dithering.espace,
+                graine: opts_dithering.graine,
+            }
+            .construire(&image_rgb8)?
+            .unwrap_or_else(|| vec![Rgb([255, 255, 255]), Rgb([0, 0, 0])]); // blanc et noir par défaut
+            // Au-delà de deux couleurs, le tramage binaire n'utiliserait pas toute la palette
+            let melange = opts_dithering
+                .melange
+                .or_else(|| (couleurs_palette.len() > 2).then_some(MelangePalette::Decalage));
+            if let Some(melange) = melange {
+                println!("Mélange des {} couleurs de la palette : {:?}", couleurs_palette.len(), melange);
+            }
+            let palette = Palette::new(couleurs_palette);
+            if opts_dithering.carte_seuil.is_some() && opts_dithering.tramage != Methode::Ordonne {
+                eprintln!("Attention : --carte-seuil n'est utilisée que par le tramage ordonné");
+            }
+            match opts_dithering.tramage {
                 Methode::Aleatoire => {
                     println!("Méthode de dithering : Aleatoire");
-                    utils::tramage_aleatoire(&mut image_rgb8); // Question 12
+                    let tramage = TramageAleatoire { melange, ecart: opts_dithering.ecart };
+                    (Box::new(tramage), palette) // Question 12
                 },
                 Methode::Ordonne => {
                     println!("Méthode de dithering : Ordonne");
-                    let matrice = utils::generer_matrice_bayer(2);
-                    utils::afficher_matrice(&matrice);
-                    utils::tramage_ordonne(&mut image_rgb8, &matrice); // Question 13
+                    let mut tramage = match &opts_dithering.carte_seuil {
+                        Some(chemin) => TramageOrdonne::depuis_carte_seuil(utils::charger_carte_seuil(chemin)?)?,
+                        None => TramageOrdonne::bayer(opts_dithering.ordre)?,
+                    };
+                    tramage.melange = melange;
+                    tramage.ecart = opts_dithering.ecart;
+                    if let Some(chemin) = &opts_dithering.carte_seuil {
+                        println!("Carte de seuils {}x{} : {}", tramage.matrice.len(), tramage.matrice[0].len(), chemin);
+                    } else if opts_dithering.ordre <= 3 {
+                        // Au-delà de 8x8, la matrice est trop grande pour être lisible dans le terminal
+                        utils::afficher_matrice(&tramage.matrice);
+                    } else {
+                        let taille = tramage.matrice.len();
+                        println!("Matrice de Bayer {}x{}", taille, taille);
+                    }
+                    (Box::new(tramage), palette) // Question 13
+                },
+                Methode::BruitBleu => {
+                    println!("Méthode de dithering : BruitBleu");
+                    let taille = opts_dithering.taille_bruit;
+                    let (matrice, erreur_cache) = bruit_bleu::matrice_void_and_cluster(taille, opts_dithering.graine, &dossier_cache());
+                    if let Some(err) = erreur_cache {
+                        eprintln!("Attention : impossible d'écrire le cache de bruit bleu {}", err);
+                    }
+                    println!("Matrice de bruit bleu {}x{}", taille, taille);
+                    let mut tramage = TramageOrdonne::depuis_matrice(matrice)?;
+                    tramage.melange = melange;
+                    tramage.ecart = opts_dithering.ecart;
+                    (Box::new(tramage), palette)
                 },
             }
         }
+        Mode::Dbs(opts_dbs) => {
+            let couleur_1 = couleurs::parser_couleur(opts_dbs.couleur_1.as_deref().unwrap_or("blanc"))?;
+            let couleur_2 = couleurs::parser_couleur(opts_dbs.couleur_2.as_deref().unwrap_or("noir"))?;
+            println!("Recherche binaire directe depuis {:?}, {} passes au plus", opts_dbs.initial, opts_dbs.passes);
+            let recherche = RechercheBinaireDirecte { initial: opts_dbs.initial, passes_max: opts_dbs.passes };
+            (Box::new(recherche), Palette::new(vec![couleur_1, couleur_2]))
+        }
+        Mode::Palettes(_) => unreachable!("la commande palettes est traitée avant le chargement de l'image"),
         Mode::DiffussionErreur(opts_diffusion_erreur) => {
             println!("Mode diffusion d'erreur");
-            let couleurs = utils::creer_liste_couleurs();
-
-            if opts_diffusion_erreur.n_couleurs > couleurs.len() {
-                eprintln!(
-                    "Erreur : Le nombre de couleurs demandé ({}) dépasse le nombre total de couleurs disponibles ({}).",
-                    opts_diffusion_erreur.n_couleurs,
-                    couleurs.len()
-                );
-                std::process::exit(1);
-            }
 
-            let mut couleurs_palette = vec![];
-            for i in 0..opts_diffusion_erreur.n_couleurs {
-                couleurs_palette.push(couleurs[i].1);
+            let couleurs_palette = SourcesPalette {
+                n_couleurs: opts_diffusion_erreur.n_couleurs,
+                couleurs: &opts_diffusion_erreur.couleurs,
+                palette_file: opts_diffusion_erreur.palette_file.as_deref(),
+                palette: opts_diffusion_erreur.palette.as_deref(),
+                auto_palette: opts_diffusion_erreur.auto_palette,
+                quantificateur: opts_diffusion_erreur.quantizer,
+                espace: opts_diffusion_erreur.espace,
+                graine: opts_diffusion_erreur.graine,
             }
+            .construire_obligatoire(&image_rgb8)?;
             println!("Les couleurs de la palette sont : {:?}", couleurs_palette);
-            
-            let matrice = match opts_diffusion_erreur.matrice {
-                MatriceDiffusionErreur::Simple2D => utils::simple_2_d(),
-                MatriceDiffusionErreur::FloydSteinberg => utils::floyd_steinberg(),
-                MatriceDiffusionErreur::JarvisJudiceNinke => utils::jarvis_judice_ninke(),
-                MatriceDiffusionErreur::Atkinson => utils::atkinson(),
-            };
+            if let Some(chemin) = &opts_diffusion_erreur.exporter_palette {
+                format_palette::sauvegarder_palette(chemin, &couleurs_palette)?;
+                println!("Palette exportée à l'emplacement : {}", chemin);
+            }
+
+            let palette = Palette::new(couleurs_palette).avec_metrique(opts_diffusion_erreur.metrique);
+            match opts_diffusion_erreur.methode {
+                MethodeDiffusion::Riemersma => {
+                    avertir_options_ignorees(&opts_diffusion_erreur.options_matrice(), "riemersma");
+                    println!("Méthode de diffusion : Riemersma (courbe de Hilbert)");
+                    (Box::new(Riemersma::default()), palette)
+                }
+                MethodeDiffusion::Ostromoukhov => {
+                    avertir_options_ignorees(&opts_diffusion_erreur.options_matrice_sans_serpentin(), "ostromoukhov");
+                    println!("Méthode de diffusion : coefficients variables d'Ostromoukhov (balayage serpentin)");
+                    (Box::new(DiffusionVariable::new(CoefficientsVariables::Ostromoukhov)), palette)
+                }
+                MethodeDiffusion::ZhouFang => {
+                    avertir_options_ignorees(&opts_diffusion_erreur.options_matrice_sans_serpentin(), "zhou-fang");
+                    println!("Méthode de diffusion : coefficients variables et modulation du seuil de Zhou-Fang (balayage serpentin)");
+                    (Box::new(DiffusionVariable::new(CoefficientsVariables::ZhouFang)), palette)
+                }
+                MethodeDiffusion::Points => {
+                    avertir_options_ignorees(&opts_diffusion_erreur.options_matrice(), "points");
+                    println!("Méthode de diffusion : diffusion par points, classes {:?}", opts_diffusion_erreur.classes);
+                    let diffusion = DiffusionPoints::new(opts_diffusion_erreur.classes);
+                    let classes: Vec<Vec<f32>> =
+                        diffusion.classes.iter().map(|ligne| ligne.iter().map(|&c| c as f32).collect()).collect();
+                    utils::afficher_matrice(&classes);
+                    (Box::new(diffusion), palette)
+                }
+                MethodeDiffusion::Matrice => {
+                    let noyau = match (&opts_diffusion_erreur.noyau, &opts_diffusion_erreur.noyau_fichier) {
+                        (Some(_), Some(_)) => {
+                            return Err(DitherError::NoyauInvalide("--noyau et --noyau-fichier sont incompatibles".to_string()))
+                        }
+                        (Some(noyau), None) => Some(noyau.clone()),
+                        (None, Some(chemin)) => Some(std::fs::read_to_string(chemin)?),
+                        (None, None) => None,
+                    };
+                    let matrice = opts_diffusion_erreur.matrice.unwrap_or(MatriceDiffusionErreur::FloydSteinberg);
+                    let diffusion = match (matrice, noyau) {
+                        (MatriceDiffusionErreur::Personnalisee, Some(noyau)) => {
+                            let diffusion = DiffusionErreur::personnalisee(&noyau, opts_diffusion_erreur.diviseur.unwrap_or(1.0))?;
+                            if let Some(avertissement) = diffusion.avertissement_somme() {
+                                eprintln!("Attention : {}", avertissement);
+                            }
+                            diffusion
+                        }
+                        (MatriceDiffusionErreur::Personnalisee, None) => {
+                            return Err(DitherError::NoyauInvalide("--matrice custom demande --noyau ou --noyau-fichier".to_string()))
+                        }
+                        (_, Some(_)) => {
+                            return Err(DitherError::NoyauInvalide("--noyau et --noyau-fichier demandent --matrice custom".to_string()))
+                        }
+                        (matrice, None) => DiffusionErreur::new(matrice)?,
+                    }
+                    .avec_lumiere_lineaire(opts_diffusion_erreur.lineaire)
+                    .avec_balayage_serpentin(opts_diffusion_erreur.serpentin);
+                    println!("Matrice de diffusion d'erreur : {:?}", matrice);
+                    utils::afficher_matrice(&diffusion.matrice);
+                    (Box::new(diffusion), palette) // Question 20
+                }
+            }
+        }
+    };
+
+    let image_rgb8 = ditherer.dither(&image_rgb8, &palette)?;
+
+    utils::sauvegarder_image_rgb8(&image_rgb8, &path_out)?; // Question 3
+    println!("Image sauvegardée avec succès à l'emplacement : {}", path_out);
+    Ok(())
+}
+
+impl OptsDiffusionErreur {
+    /// Options propres à la diffusion par matrice renseignées sur la ligne de commande
+    fn options_matrice(&self) -> Vec<&'static str> {
+        [
+            ("--matrice", self.matrice.is_some()),
+            ("--noyau", self.noyau.is_some()),
+            ("--noyau-fichier", self.noyau_fichier.is_some()),
+            ("--diviseur", self.diviseur.is_some()),
+            ("--lineaire", self.lineaire),
+            ("--serpentin", self.serpentin),
+        ]
+        .into_iter()
+        .filter_map(|(option, renseignee)| renseignee.then_some(option))
+        .collect()
+    }
+
+    /// Options propres à la diffusion par matrice, hors `--serpentin` pour les méthodes qui
+    /// balaient toujours l'image en serpentin
+    fn options_matrice_sans_serpentin(&self) -> Vec<&'static str> {
+        let mut options = self.options_matrice();
+        options.retain(|&option| option != "--serpentin");
+        options
+    }
+}
+
+/// Signaler les options de la ligne de commande qu'une méthode de diffusion n'utilise pas
+fn avertir_options_ignorees(options: &[&str], methode: &str) {
+    if !options.is_empty() {
+        eprintln!("Attention : {} ignorée(s) par la méthode {}", options.join(", "), methode);
+    }
+}
+
+/// Options de la ligne de commande permettant de choisir une palette
+#[derive(Default)]
+struct SourcesPalette<'a> {
+    n_couleurs: Option<usize>,
+    couleurs: &'a [String],
+    palette_file: Option<&'a str>,
+    palette: Option<&'a str>,
+    auto_palette: Option<usize>,
+    quantificateur: Quantificateur,
+    espace: EspaceCouleur,
+    graine: u64,
+}
+
+impl SourcesPalette<'_> {
+    /// Construire la palette à partir de l'unique option renseignée, ou `None` si aucune ne l'est
+    fn construire(&self, image_rgb8: &RgbImage) -> Result<Option<Vec<Rgb<u8>>>, DitherError> {
+        let nombre_sources = self.n_couleurs.is_some() as usize
+            + !self.couleurs.is_empty() as usize
+            + self.palette_file.is_some() as usize
+            + self.palette.is_some() as usize
+            + self.auto_palette.is_some() as usize;
+        if nombre_sources > 1 {
+            return Err(DitherError::PaletteInvalide(
+                "les options --n-couleurs, --couleurs, --palette-file, --palette et --auto-palette ne peuvent pas être utilisées ensemble".to_string(),
+            ));
+        }
 
-            println!("Matrice de diffusion d'erreur : {:?}", opts_diffusion_erreur.matrice);
-            utils::afficher_matrice(&matrice);
-            //utils::diffusion_erreur(&mut image_rgb8); //question 16
-            utils::diffusion_erreur_generique(&mut image_rgb8, couleurs_palette, matrice); // Question 20
+        if let Some(n_couleurs) = self.n_couleurs {
+            return palette_predefinie(n_couleurs).map(Some);
+        }
+        if let Some(n_couleurs) = self.auto_palette {
+            if n_couleurs < 2 {
+                return Err(DitherError::PaletteInvalide(format!(
+                    "--auto-palette demande au moins 2 couleurs, {} demandée(s)",
+                    n_couleurs
+                )));
+            }
+            let palette = self.quantificateur.generer_palette(image_rgb8, n_couleurs, self.espace, self.graine);
+            return Ok(Some(palette.couleurs().to_vec()));
+        }
+        if let Some(nom) = self.palette {
+            return palettes_retro::palette_retro(nom)
+                .map(|palette| Some(palette.couleurs()))
+                .ok_or_else(|| {
+                    DitherError::PaletteInvalide(format!(
+                        "la palette '{}' n'existe pas, la commande palettes liste les palettes disponibles",
+                        nom
+                    ))
+                });
+        }
+        if let Some(chemin) = self.palette_file {
+            return format_palette::charger_palette(chemin).map(Some);
+        }
+        if !self.couleurs.is_empty() {
+            let mut couleurs_palette = vec![];
+            for liste in self.couleurs {
+                couleurs_palette.extend(couleurs::parser_liste_couleurs(liste)?);
+            }
+            return Ok(Some(couleurs_palette));
         }
+        Ok(None)
+    }
+
+    /// Construire la palette en exigeant qu'une des options soit renseignée
+    fn construire_obligatoire(&self, image_rgb8: &RgbImage) -> Result<Vec<Rgb<u8>>, DitherError> {
+        self.construire(image_rgb8)?.ok_or_else(|| {
+            DitherError::PaletteInvalide(
+                "une palette doit être donnée avec --n-couleurs, --couleurs, --palette-file, --palette ou --auto-palette".to_string(),
+            )
+        })
+    }
+}
+
+/// Choisir le papier (couleur la plus claire) et l'encre (couleur la plus sombre) d'une palette
+fn papier_et_encre(couleurs_palette: &[Rgb<u8>]) -> Result<(Rgb<u8>, Rgb<u8>), DitherError> {
+    if couleurs_palette.len() < 2 {
+        return Err(DitherError::PaletteInvalide(format!(
+            "2 couleurs sont nécessaires, la palette n'en contient que {}",
+            couleurs_palette.len()
+        )));
+    }
+    let par_luminosite = |a: &&Rgb<u8>, b: &&Rgb<u8>| utils::luminosite_pixel(a).total_cmp(&utils::luminosite_pixel(b));
+    let papier = couleurs_palette.iter().max_by(par_luminosite).copied().unwrap_or(Rgb([255, 255, 255]));
+    let encre = couleurs_palette.iter().min_by(par_luminosite).copied().unwrap_or(Rgb([0, 0, 0]));
+    Ok((papier, encre))
+}
+
+/// Récupérer les n premières couleurs de la liste prédéfinie
+fn palette_predefinie(n_couleurs: usize) -> Result<Vec<Rgb<u8>>, DitherError> {
+    let couleurs = utils::creer_liste_couleurs();
+
+    if n_couleurs > couleurs.len() {
+        return Err(DitherError::PaletteInvalide(format!(
+            "Le nombre de couleurs demandé ({}) dépasse le nombre total de couleurs disponibles ({}).",
+            n_couleurs,
+            couleurs.len()
+        )));
     }
 
-    utils::sauvegarder_image_rgb8(&image_rgb8, &path_out); // Question 3
+    Ok(couleurs.iter().take(n_couleurs).map(|(_, rgb)| *rgb).collect())
 }
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use image::{Rgb, RgbImage};
+
+use crate::erreur::DitherError;
+use crate::metrique::MetriqueCouleur;
+use crate::palette::Palette;
+use crate::utils;
+
+/// Nombre de couleurs d'un plan de mélange de Yliluoma : autant de niveaux de seuil distincts
+const TAILLE_PLAN: usize = 64;
+
+/// Manière de mélanger les couleurs d'une palette quelconque dans les tramages ordonné et aléatoire
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum MelangePalette {
+    /// Décalage de chaque composante par le seuil (`couleur + ecart * (seuil - 0.5)`),
+    /// puis recherche de la couleur la plus proche
+    #[default]
+    Decalage,
+    /// Plan de mélange de Joel Yliluoma (algorithme 2) : chaque couleur est approchée par une suite
+    /// de couleurs de la palette triée par luminosité, dans laquelle le seuil choisit
+    Yliluoma,
+}
+
+// Implémentation de FromStr pour Enum
+impl FromStr for MelangePalette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "decalage" => Ok(MelangePalette::Decalage),
+            "yliluoma" => Ok(MelangePalette::Yliluoma),
+            _ => Err(format!("Méthode de mélange invalide: {}", s)),
+        }
+    }
+}
+
+/// Écart de seuil par défaut du mélange par décalage : plus la palette est riche,
+/// plus ses couleurs sont proches les unes des autres
+pub fn ecart_par_defaut(palette: &Palette) -> f32 {
+    255.0 / (palette.len().max(1) as f32).cbrt()
+}
+
+impl MelangePalette {
+    /// Tramer une image vers les couleurs de la palette, `seuil(x, y)` donnant pour chaque pixel
+    /// un seuil entre 0 et 1 (matrice de Bayer, bruit aléatoire...). `ecart` n'est utilisé que
+    /// par le mélange par décalage
+    pub fn tramer<F>(&self, img: &RgbImage, palette: &Palette, ecart: f32, mut seuil: F) -> Result<RgbImage, DitherError>
+    where
+        F: FnMut(u32, u32) -> f32,
+    {
+        if palette.is_empty() {
+            return Err(DitherError::PaletteInvalide("la palette ne contient aucune couleur".to_string()));
+        }
+        let metrique = palette.metrique();
+        let coordonnees_palette: Vec<[f32; 3]> = palette.couleurs().iter().map(|c| metrique.coordonnees(c)).collect();
+        let mut image_rgb8 = img.clone();
+
+        match self {
+            MelangePalette::Decalage => {
+                for (x, y, pixel) in image_rgb8.enumerate_pixels_mut() {
+                    let decalage = ecart * (seuil(x, y) - 0.5);
+                    let decale = Rgb(pixel.0.map(|c| (c as f32 + decalage).round().clamp(0.0, 255.0) as u8));
+                    let indice = indice_plus_proche(&metrique.coordonnees(&decale), &coordonnees_palette, metrique);
+                    *pixel = palette.couleurs()[indice];
+                }
+            }
+            MelangePalette::Yliluoma => {
+                // Les plans sont mis en cache par couleur réduite à 6 bits par composante : un plan de
+                // 64 couleurs ne distingue de toute façon pas des nuances plus fines
+                let mut plans: HashMap<[u8; 3], Vec<usize>> = HashMap::new();
+                for (x, y, pixel) in image_rgb8.enumerate_pixels_mut() {
+                    let cle = pixel.0.map(|c| (c & 0xFC) | 0x02);
+                    let plan = plans
+                        .entry(cle)
+                        .or_insert_with(|| plan_yliluoma(&Rgb(cle), palette.couleurs(), metrique));
+                    let rang = ((seuil(x, y) * plan.len() as f32) as usize).min(plan.len() - 1);
+                    *pixel = palette.couleurs()[plan[rang]];
+                }
+            }
+        }
+        Ok(image_rgb8)
+    }
+}
+
+/// Indice de la couleur de la palette la plus proche, coordonnées déjà converties pour la métrique
+fn indice_plus_proche(coordonnees: &[f32; 3], coordonnees_palette: &[[f32; 3]], metrique: MetriqueCouleur) -> usize {
+    let mut distance_min = f32::MAX;
+    let mut plus_proche = 0;
+    for (i, couleur) in coordonnees_palette.iter().enumerate() {
+        let distance = metrique.distance_coordonnees(coordonnees, couleur);
+        if distance < distance_min {
+            distance_min = distance;
+            plus_proche = i;
+        }
+    }
+    plus_proche
+}
+
+/// Construire le plan de mélange d'une couleur : à chaque étape, on ajoute la couleur de la palette
+/// (en 1, 2, 4... exemplaires) dont l'ajout rapproche le plus la moyenne du plan de la couleur visée.
+/// Le plan est ensuite trié par luminosité pour que les seuils croissants parcourent les couleurs
+/// de la plus sombre à la plus claire
+fn plan_yliluoma(pixel: &Rgb<u8>, couleurs_palette: &[Rgb<u8>], metrique: MetriqueCouleur) -> Vec<usize> {
+    let coordonnees_pixel = metrique.coordonnees(pixel);
+    let mut plan = Vec::with_capacity(TAILLE_PLAN);
+    let mut somme = [0.0f32; 3];
+
+    while plan.len() < TAILLE_PLAN {
+        let total = plan.len();
+        let max_essais = total.max(1);
+        let mut penalite_min = f32::MAX;
+        let mut choix = (0, 1);
+        for (i, couleur) in couleurs_palette.iter().enumerate() {
+            let mut quantite = 1;
+            while quantite <= max_essais {
+                let moyenne = Rgb(std::array::from_fn(|c| {
+                    let valeur = (somme[c] + couleur[c] as f32 * quantite as f32) / (total + quantite) as f32;
+                    valeur.round() as u8
+                }));
+                let penalite = metrique.distance_coordonnees(&coordonnees_pixel, &metrique.coordonnees(&moyenne));
+                if penalite < penalite_min {
+                    penalite_min = penalite;
+                    choix = (i, quantite);
+                }
+                quantite *= 2;
+            }
+        }
+
+        let (indice, quantite) = choix;
+        let quantite = quantite.min(TAILLE_PLAN - total);
+        for (total_composante, composante) in somme.iter_mut().zip(couleurs_palette[indice].0) {
+            *total_composante += composante as f32 * quantite as f32;
+        }
+        plan.extend(std::iter::repeat_n(indice, quantite));
+    }
+
+    plan.sort_by(|a, b| {
+        utils::luminosite_pixel(&couleurs_palette[*a]).total_cmp(&utils::luminosite_pixel(&couleurs_palette[*b]))
+    });
+    plan
+}
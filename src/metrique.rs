@@ -0,0 +1,140 @@
+use std::f32::consts::PI;
+use std::str::FromStr;
+
+use image::Rgb;
+
+use crate::espaces_couleur;
+
+/// Mesure de la différence entre deux couleurs, utilisée pour la recherche de la couleur la plus proche
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum MetriqueCouleur {
+    /// Distance euclidienne sur les composantes sRGB
+    #[default]
+    Euclidienne,
+    /// Distance RGB pondérée selon la quantité de rouge ("redmean")
+    RedMean,
+    /// ΔE 1976 : distance euclidienne dans CIELAB
+    DeltaE76,
+    /// ΔE 2000 (CIEDE2000)
+    Ciede2000,
+    /// Distance euclidienne dans OKLab
+    Oklab,
+}
+
+// Implémentation de FromStr pour Enum
+impl FromStr for MetriqueCouleur {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "euclidienne" | "rgb" => Ok(MetriqueCouleur::Euclidienne),
+            "redmean" => Ok(MetriqueCouleur::RedMean),
+            "deltae76" | "lab" => Ok(MetriqueCouleur::DeltaE76),
+            "ciede2000" | "deltae2000" => Ok(MetriqueCouleur::Ciede2000),
+            "oklab" => Ok(MetriqueCouleur::Oklab),
+            _ => Err(format!("Métrique de couleur invalide: {}", s)),
+        }
+    }
+}
+
+impl MetriqueCouleur {
+    /// Coordonnées d'une couleur dans l'espace où la métrique est calculée
+    pub fn coordonnees(&self, couleur: &Rgb<u8>) -> [f32; 3] {
+        match self {
+            MetriqueCouleur::Euclidienne | MetriqueCouleur::RedMean => {
+                [couleur[0] as f32, couleur[1] as f32, couleur[2] as f32]
+            }
+            MetriqueCouleur::DeltaE76 | MetriqueCouleur::Ciede2000 => espaces_couleur::srgb_vers_lab(couleur),
+            MetriqueCouleur::Oklab => espaces_couleur::srgb_vers_oklab(couleur),
+        }
+    }
+
+    /// Distance entre deux couleurs déjà converties par [`MetriqueCouleur::coordonnees`]
+    pub fn distance_coordonnees(&self, a: &[f32; 3], b: &[f32; 3]) -> f32 {
+        match self {
+            MetriqueCouleur::Euclidienne | MetriqueCouleur::DeltaE76 | MetriqueCouleur::Oklab => {
+                ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+            }
+            MetriqueCouleur::RedMean => {
+                let moyenne_rouge = (a[0] + b[0]) / 2.0;
+                let (dr, dg, db) = (a[0] - b[0], a[1] - b[1], a[2] - b[2]);
+                ((2.0 + moyenne_rouge / 256.0) * dr * dr
+                    + 4.0 * dg * dg
+                    + (2.0 + (255.0 - moyenne_rouge) / 256.0) * db * db)
+                    .sqrt()
+            }
+            MetriqueCouleur::Ciede2000 => ciede2000(a, b),
+        }
+    }
+
+    /// Distance entre deux couleurs sRGB
+    pub fn distance(&self, a: &Rgb<u8>, b: &Rgb<u8>) -> f32 {
+        self.distance_coordonnees(&self.coordonnees(a), &self.coordonnees(b))
+    }
+}
+
+/// Différence de couleur CIEDE2000 entre deux couleurs CIELAB
+fn ciede2000(lab_1: &[f32; 3], lab_2: &[f32; 3]) -> f32 {
+    let [l1, a1, b1] = *lab_1;
+    let [l2, a2, b2] = *lab_2;
+
+    let c1 = (a1 * a1 + b1 * b1).sqrt();
+    let c2 = (a2 * a2 + b2 * b2).sqrt();
+    let c_moyen = (c1 + c2) / 2.0;
+    let g = 0.5 * (1.0 - (c_moyen.powi(7) / (c_moyen.powi(7) + 25f32.powi(7))).sqrt());
+
+    let a1p = (1.0 + g) * a1;
+    let a2p = (1.0 + g) * a2;
+    let c1p = (a1p * a1p + b1 * b1).sqrt();
+    let c2p = (a2p * a2p + b2 * b2).sqrt();
+    let teinte = |b: f32, ap: f32| {
+        if b == 0.0 && ap == 0.0 {
+            0.0
+        } else {
+            b.atan2(ap).to_degrees().rem_euclid(360.0)
+        }
+    };
+    let h1p = teinte(b1, a1p);
+    let h2p = teinte(b2, a2p);
+
+    let delta_l = l2 - l1;
+    let delta_c = c2p - c1p;
+    let delta_h = if c1p * c2p == 0.0 {
+        0.0
+    } else if (h2p - h1p).abs() <= 180.0 {
+        h2p - h1p
+    } else if h2p - h1p > 180.0 {
+        h2p - h1p - 360.0
+    } else {
+        h2p - h1p + 360.0
+    };
+    let delta_h_grand = 2.0 * (c1p * c2p).sqrt() * (delta_h.to_radians() / 2.0).sin();
+
+    let l_moyen = (l1 + l2) / 2.0;
+    let c_moyen_p = (c1p + c2p) / 2.0;
+    let h_moyen = if c1p * c2p == 0.0 {
+        h1p + h2p
+    } else if (h1p - h2p).abs() <= 180.0 {
+        (h1p + h2p) / 2.0
+    } else if h1p + h2p < 360.0 {
+        (h1p + h2p + 360.0) / 2.0
+    } else {
+        (h1p + h2p - 360.0) / 2.0
+    };
+
+    let t = 1.0 - 0.17 * (h_moyen - 30.0).to_radians().cos()
+        + 0.24 * (2.0 * h_moyen).to_radians().cos()
+        + 0.32 * (3.0 * h_moyen + 6.0).to_radians().cos()
+        - 0.20 * (4.0 * h_moyen - 63.0).to_radians().cos();
+    let delta_theta = 30.0 * (-((h_moyen - 275.0) / 25.0).powi(2)).exp();
+    let r_c = 2.0 * (c_moyen_p.powi(7) / (c_moyen_p.powi(7) + 25f32.powi(7))).sqrt();
+    let s_l = 1.0 + 0.015 * (l_moyen - 50.0).powi(2) / (20.0 + (l_moyen - 50.0).powi(2)).sqrt();
+    let s_c = 1.0 + 0.045 * c_moyen_p;
+    let s_h = 1.0 + 0.015 * c_moyen_p * t;
+    let r_t = -(2.0 * delta_theta * PI / 180.0).sin() * r_c;
+
+    let terme_l = delta_l / s_l;
+    let terme_c = delta_c / s_c;
+    let terme_h = delta_h_grand / s_h;
+    (terme_l * terme_l + terme_c * terme_c + terme_h * terme_h + r_t * terme_c * terme_h).sqrt()
+}
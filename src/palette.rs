@@ -0,0 +1,56 @@
+use image::Rgb;
+
+use crate::metrique::MetriqueCouleur;
+use crate::utils;
+
+/// Palette de couleurs vers laquelle une image est réduite, avec la métrique
+/// utilisée pour trouver la couleur la plus proche d'un pixel
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    couleurs: Vec<Rgb<u8>>,
+    metrique: MetriqueCouleur,
+}
+
+impl Palette {
+    /// Créer une palette à partir d'une liste de couleurs, comparées par distance euclidienne
+    pub fn new(couleurs: Vec<Rgb<u8>>) -> Self {
+        Palette { couleurs, metrique: MetriqueCouleur::default() }
+    }
+
+    /// Changer la métrique utilisée pour comparer les couleurs
+    pub fn avec_metrique(mut self, metrique: MetriqueCouleur) -> Self {
+        self.metrique = metrique;
+        self
+    }
+
+    /// Récupérer la métrique de la palette
+    pub fn metrique(&self) -> MetriqueCouleur {
+        self.metrique
+    }
+
+    /// Récupérer les couleurs de la palette
+    pub fn couleurs(&self) -> &[Rgb<u8>] {
+        &self.couleurs
+    }
+
+    /// Nombre de couleurs de la palette
+    pub fn len(&self) -> usize {
+        self.couleurs.len()
+    }
+
+    /// Indique si la palette ne contient aucune couleur
+    pub fn is_empty(&self) -> bool {
+        self.couleurs.is_empty()
+    }
+
+    /// Trouver la couleur de la palette la plus proche d'un pixel
+    pub fn couleur_la_plus_proche(&self, pixel: &Rgb<u8>) -> Rgb<u8> {
+        utils::couleur_la_plus_proche(pixel, &self.couleurs, self.metrique)
+    }
+}
+
+impl From<Vec<Rgb<u8>>> for Palette {
+    fn from(couleurs: Vec<Rgb<u8>>) -> Self {
+        Palette::new(couleurs)
+    }
+}
@@ -0,0 +1,251 @@
+use image::Rgb;
+
+/// Palette matérielle prédéfinie, les couleurs étant stockées en hexadécimal 0xRRGGBB
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaletteRetro {
+    pub nom: &'static str,
+    pub description: &'static str,
+    couleurs: &'static [u32],
+}
+
+impl PaletteRetro {
+    /// Récupérer les couleurs de la palette
+    pub fn couleurs(&self) -> Vec<Rgb<u8>> {
+        self.couleurs
+            .iter()
+            .map(|hex| Rgb([(hex >> 16) as u8, (hex >> 8) as u8, *hex as u8]))
+            .collect()
+    }
+}
+
+/// Palettes des anciens ordinateurs et consoles
+pub const PALETTES_RETRO: &[PaletteRetro] = &[
+    PaletteRetro {
+        nom: "cga",
+        description: "CGA 16 couleurs (mode texte)",
+        couleurs: &[
+            0x000000, 0x0000AA, 0x00AA00, 0x00AAAA, 0xAA0000, 0xAA00AA, 0xAA5500, 0xAAAAAA,
+            0x555555, 0x5555FF, 0x55FF55, 0x55FFFF, 0xFF5555, 0xFF55FF, 0xFFFF55, 0xFFFFFF,
+        ],
+    },
+    PaletteRetro {
+        nom: "cga-0",
+        description: "CGA mode 4, palette 0 basse intensité",
+        couleurs: &[
+            0x000000, 0x00AA00, 0xAA0000, 0xAA5500,
+        ],
+    },
+    PaletteRetro {
+        nom: "cga-0-clair",
+        description: "CGA mode 4, palette 0 haute intensité",
+        couleurs: &[
+            0x000000, 0x55FF55, 0xFF5555, 0xFFFF55,
+        ],
+    },
+    PaletteRetro {
+        nom: "cga-1",
+        description: "CGA mode 4, palette 1 basse intensité",
+        couleurs: &[
+            0x000000, 0x00AAAA, 0xAA00AA, 0xAAAAAA,
+        ],
+    },
+    PaletteRetro {
+        nom: "cga-1-clair",
+        description: "CGA mode 4, palette 1 haute intensité",
+        couleurs: &[
+            0x000000, 0x55FFFF, 0xFF55FF, 0xFFFFFF,
+        ],
+    },
+    PaletteRetro {
+        nom: "cga-5",
+        description: "CGA mode 5 basse intensité",
+        couleurs: &[
+            0x000000, 0x00AAAA, 0xAA0000, 0xAAAAAA,
+        ],
+    },
+    PaletteRetro {
+        nom: "cga-5-clair",
+        description: "CGA mode 5 haute intensité",
+        couleurs: &[
+            0x000000, 0x55FFFF, 0xFF5555, 0xFFFFFF,
+        ],
+    },
+    PaletteRetro {
+        nom: "ega",
+        description: "EGA 16 couleurs par défaut",
+        couleurs: &[
+            0x000000, 0x0000AA, 0x00AA00, 0x00AAAA, 0xAA0000, 0xAA00AA, 0xAA5500, 0xAAAAAA,
+            0x555555, 0x5555FF, 0x55FF55, 0x55FFFF, 0xFF5555, 0xFF55FF, 0xFFFF55, 0xFFFFFF,
+        ],
+    },
+    PaletteRetro {
+        nom: "ega-64",
+        description: "EGA 64 couleurs (2 bits par composante)",
+        couleurs: &[
+            0x000000, 0x0000AA, 0x00AA00, 0x00AAAA, 0xAA0000, 0xAA00AA, 0xAAAA00, 0xAAAAAA,
+            0x000055, 0x0000FF, 0x00AA55, 0x00AAFF, 0xAA0055, 0xAA00FF, 0xAAAA55, 0xAAAAFF,
+            0x005500, 0x0055AA, 0x00FF00, 0x00FFAA, 0xAA5500, 0xAA55AA, 0xAAFF00, 0xAAFFAA,
+            0x005555, 0x0055FF, 0x00FF55, 0x00FFFF, 0xAA5555, 0xAA55FF, 0xAAFF55, 0xAAFFFF,
+            0x550000, 0x5500AA, 0x55AA00, 0x55AAAA, 0xFF0000, 0xFF00AA, 0xFFAA00, 0xFFAAAA,
+            0x550055, 0x5500FF, 0x55AA55, 0x55AAFF, 0xFF0055, 0xFF00FF, 0xFFAA55, 0xFFAAFF,
+            0x555500, 0x5555AA, 0x55FF00, 0x55FFAA, 0xFF5500, 0xFF55AA, 0xFFFF00, 0xFFFFAA,
+            0x555555, 0x5555FF, 0x55FF55, 0x55FFFF, 0xFF5555, 0xFF55FF, 0xFFFF55, 0xFFFFFF,
+        ],
+    },
+    PaletteRetro {
+        nom: "gameboy",
+        description: "Game Boy DMG, 4 verts",
+        couleurs: &[
+            0x0F380F, 0x306230, 0x8BAC0F, 0x9BBC0F,
+        ],
+    },
+    PaletteRetro {
+        nom: "pico-8",
+        description: "PICO-8",
+        couleurs: &[
+            0x000000, 0x1D2B53, 0x7E2553, 0x008751, 0xAB5236, 0x5F574F, 0xC2C3C7, 0xFFF1E8,
+            0xFF004D, 0xFFA300, 0xFFEC27, 0x00E436, 0x29ADFF, 0x83769C, 0xFF77A8, 0xFFCCAA,
+        ],
+    },
+    PaletteRetro {
+        nom: "c64",
+        description: "Commodore 64 (Pepto)",
+        couleurs: &[
+            0x000000, 0xFFFFFF, 0x68372B, 0x70A4B2, 0x6F3D86, 0x588D43, 0x352879, 0xB8C76F,
+            0x6F4F25, 0x433900, 0x9A6759, 0x444444, 0x6C6C6C, 0x9AD284, 0x6C5EB5, 0x959595,
+        ],
+    },
+    PaletteRetro {
+        nom: "nes",
+        description: "NES (PPU 2C02)",
+        couleurs: &[
+            0x7C7C7C, 0x0000FC, 0x0000BC, 0x4428BC, 0x940084, 0xA80020, 0xA81000, 0x881400,
+            0x503000, 0x007800, 0x006800, 0x005800, 0x004058, 0x000000, 0x000000, 0x000000,
+            0xBCBCBC, 0x0078F8, 0x0058F8, 0x6844FC, 0xD800CC, 0xE40058, 0xF83800, 0xE45C10,
+            0xAC7C00, 0x00B800, 0x00A800, 0x00A844, 0x008888, 0x000000, 0x000000, 0x000000,
+            0xF8F8F8, 0x3CBCFC, 0x6888FC, 0x9878F8, 0xF878F8, 0xF85898, 0xF87858, 0xFCA044,
+            0xF8B800, 0xB8F818, 0x58D854, 0x58F898, 0x00E8D8, 0x787878, 0x000000, 0x000000,
+            0xFCFCFC, 0xA4E4FC, 0xB8B8F8, 0xD8B8F8, 0xF8B8F8, 0xF8A4C0, 0xF0D0B0, 0xFCE0A8,
+            0xF8D878, 0xD8F878, 0xB8F8B8, 0xB8F8D8, 0x00FCFC, 0xF8D8F8, 0x000000, 0x000000,
+        ],
+    },
+    PaletteRetro {
+        nom: "zx-spectrum",
+        description: "ZX Spectrum, intensités normale et brillante",
+        couleurs: &[
+            0x000000, 0x0000D7, 0xD70000, 0xD700D7, 0x00D700, 0x00D7D7, 0xD7D700, 0xD7D7D7,
+            0x0000FF, 0xFF0000, 0xFF00FF, 0x00FF00, 0x00FFFF, 0xFFFF00, 0xFFFFFF,
+        ],
+    },
+    PaletteRetro {
+        nom: "zx-spectrum-normal",
+        description: "ZX Spectrum, intensité normale",
+        couleurs: &[
+            0x000000, 0x0000D7, 0xD70000, 0xD700D7, 0x00D700, 0x00D7D7, 0xD7D700, 0xD7D7D7,
+        ],
+    },
+    PaletteRetro {
+        nom: "zx-spectrum-clair",
+        description: "ZX Spectrum, intensité brillante",
+        couleurs: &[
+            0x000000, 0x0000FF, 0xFF0000, 0xFF00FF, 0x00FF00, 0x00FFFF, 0xFFFF00, 0xFFFFFF,
+        ],
+    },
+    PaletteRetro {
+        nom: "apple-ii",
+        description: "Apple II basse résolution",
+        couleurs: &[
+            0x000000, 0x6C2940, 0x403578, 0xD93CF0, 0x135740, 0x808080, 0x2997F0, 0xBFB4F8,
+            0x404B07, 0xD9680F, 0x808080, 0xECA8BF, 0x26C30F, 0xBFCA87, 0x93D6BF, 0xFFFFFF,
+        ],
+    },
+    PaletteRetro {
+        nom: "amstrad-cpc",
+        description: "Amstrad CPC, 27 couleurs",
+        couleurs: &[
+            0x000000, 0x000080, 0x0000FF, 0x800000, 0x800080, 0x8000FF, 0xFF0000, 0xFF0080,
+            0xFF00FF, 0x008000, 0x008080, 0x0080FF, 0x808000, 0x808080, 0x8080FF, 0xFF8000,
+            0xFF8080, 0xFF80FF, 0x00FF00, 0x00FF80, 0x00FFFF, 0x80FF00, 0x80FF80, 0x80FFFF,
+            0xFFFF00, 0xFFFF80, 0xFFFFFF,
+        ],
+    },
+    PaletteRetro {
+        nom: "msx",
+        description: "MSX (TMS9918)",
+        couleurs: &[
+            0x000000, 0x3EB849, 0x74D07D, 0x5955E0, 0x8076F1, 0xB95E51, 0x65DBEF, 0xDB6559,
+            0xFF897D, 0xCCC35E, 0xDED087, 0x3AA241, 0xB766B5, 0xCCCCCC, 0xFFFFFF,
+        ],
+    },
+    PaletteRetro {
+        nom: "macintosh",
+        description: "Macintosh 16 couleurs (System 4.1)",
+        couleurs: &[
+            0xFFFFFF, 0xFBF305, 0xFF6403, 0xDD0907, 0xF20884, 0x4700A5, 0x0000D3, 0x02ABEA,
+            0x1FB714, 0x006412, 0x562C05, 0x90713A, 0xC0C0C0, 0x808080, 0x404040, 0x000000,
+        ],
+    },
+    PaletteRetro {
+        nom: "windows-16",
+        description: "Windows 16 couleurs (VGA)",
+        couleurs: &[
+            0x000000, 0x800000, 0x008000, 0x808000, 0x000080, 0x800080, 0x008080, 0xC0C0C0,
+            0x808080, 0xFF0000, 0x00FF00, 0xFFFF00, 0x0000FF, 0xFF00FF, 0x00FFFF, 0xFFFFFF,
+        ],
+    },
+    PaletteRetro {
+        nom: "windows-20",
+        description: "Windows 20 couleurs statiques",
+        couleurs: &[
+            0x000000, 0x800000, 0x008000, 0x808000, 0x000080, 0x800080, 0x008080, 0xC0C0C0,
+            0xC0DCC0, 0xA6CAF0, 0xFFFBF0, 0xA0A0A4, 0x808080, 0xFF0000, 0x00FF00, 0xFFFF00,
+            0x0000FF, 0xFF00FF, 0x00FFFF, 0xFFFFFF,
+        ],
+    },
+    PaletteRetro {
+        nom: "web-216",
+        description: "Palette web-safe, 216 couleurs",
+        couleurs: &[
+            0x000000, 0x000033, 0x000066, 0x000099, 0x0000CC, 0x0000FF, 0x003300, 0x003333,
+            0x003366, 0x003399, 0x0033CC, 0x0033FF, 0x006600, 0x006633, 0x006666, 0x006699,
+            0x0066CC, 0x0066FF, 0x009900, 0x009933, 0x009966, 0x009999, 0x0099CC, 0x0099FF,
+            0x00CC00, 0x00CC33, 0x00CC66, 0x00CC99, 0x00CCCC, 0x00CCFF, 0x00FF00, 0x00FF33,
+            0x00FF66, 0x00FF99, 0x00FFCC, 0x00FFFF, 0x330000, 0x330033, 0x330066, 0x330099,
+            0x3300CC, 0x3300FF, 0x333300, 0x333333, 0x333366, 0x333399, 0x3333CC, 0x3333FF,
+            0x336600, 0x336633, 0x336666, 0x336699, 0x3366CC, 0x3366FF, 0x339900, 0x339933,
+            0x339966, 0x339999, 0x3399CC, 0x3399FF, 0x33CC00, 0x33CC33, 0x33CC66, 0x33CC99,
+            0x33CCCC, 0x33CCFF, 0x33FF00, 0x33FF33, 0x33FF66, 0x33FF99, 0x33FFCC, 0x33FFFF,
+            0x660000, 0x660033, 0x660066, 0x660099, 0x6600CC, 0x6600FF, 0x663300, 0x663333,
+            0x663366, 0x663399, 0x6633CC, 0x6633FF, 0x666600, 0x666633, 0x666666, 0x666699,
+            0x6666CC, 0x6666FF, 0x669900, 0x669933, 0x669966, 0x669999, 0x6699CC, 0x6699FF,
+            0x66CC00, 0x66CC33, 0x66CC66, 0x66CC99, 0x66CCCC, 0x66CCFF, 0x66FF00, 0x66FF33,
+            0x66FF66, 0x66FF99, 0x66FFCC, 0x66FFFF, 0x990000, 0x990033, 0x990066, 0x990099,
+            0x9900CC, 0x9900FF, 0x993300, 0x993333, 0x993366, 0x993399, 0x9933CC, 0x9933FF,
+            0x996600, 0x996633, 0x996666, 0x996699, 0x9966CC, 0x9966FF, 0x999900, 0x999933,
+            0x999966, 0x999999, 0x9999CC, 0x9999FF, 0x99CC00, 0x99CC33, 0x99CC66, 0x99CC99,
+            0x99CCCC, 0x99CCFF, 0x99FF00, 0x99FF33, 0x99FF66, 0x99FF99, 0x99FFCC, 0x99FFFF,
+            0xCC0000, 0xCC0033, 0xCC0066, 0xCC0099, 0xCC00CC, 0xCC00FF, 0xCC3300, 0xCC3333,
+            0xCC3366, 0xCC3399, 0xCC33CC, 0xCC33FF, 0xCC6600, 0xCC6633, 0xCC6666, 0xCC6699,
+            0xCC66CC, 0xCC66FF, 0xCC9900, 0xCC9933, 0xCC9966, 0xCC9999, 0xCC99CC, 0xCC99FF,
+            0xCCCC00, 0xCCCC33, 0xCCCC66, 0xCCCC99, 0xCCCCCC, 0xCCCCFF, 0xCCFF00, 0xCCFF33,
+            0xCCFF66, 0xCCFF99, 0xCCFFCC, 0xCCFFFF, 0xFF0000, 0xFF0033, 0xFF0066, 0xFF0099,
+            0xFF00CC, 0xFF00FF, 0xFF3300, 0xFF3333, 0xFF3366, 0xFF3399, 0xFF33CC, 0xFF33FF,
+            0xFF6600, 0xFF6633, 0xFF6666, 0xFF6699, 0xFF66CC, 0xFF66FF, 0xFF9900, 0xFF9933,
+            0xFF9966, 0xFF9999, 0xFF99CC, 0xFF99FF, 0xFFCC00, 0xFFCC33, 0xFFCC66, 0xFFCC99,
+            0xFFCCCC, 0xFFCCFF, 0xFFFF00, 0xFFFF33, 0xFFFF66, 0xFFFF99, 0xFFFFCC, 0xFFFFFF,
+        ],
+    },
+];
+
+/// Rechercher une palette prédéfinie par son nom (insensible à la casse)
+pub fn palette_retro(nom: &str) -> Option<&'static PaletteRetro> {
+    PALETTES_RETRO.iter().find(|palette| palette.nom.eq_ignore_ascii_case(nom))
+}
+
+/// Afficher une palette sous forme de nuancier dans un terminal compatible 24 bits
+pub fn afficher_nuancier(couleurs: &[Rgb<u8>]) {
+    for couleur in couleurs {
+        print!("\x1b[48;2;{};{};{}m   \x1b[0m", couleur[0], couleur[1], couleur[2]);
+    }
+    println!();
+}
@@ -0,0 +1,487 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use image::{Rgb, RgbImage};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::espaces_couleur::EspaceCouleur;
+use crate::palette::Palette;
+
+/// Algorithme de génération d'une palette adaptée à une image
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Quantificateur {
+    /// Découpage récursif à la médiane de l'axe le plus étendu (Heckbert)
+    #[default]
+    MedianCut,
+    /// K-moyennes avec initialisation k-means++
+    KMeans,
+    /// Minimisation de la variance par découpage de boîtes (Xiaolin Wu)
+    Wu,
+    /// Arbre octal dont les feuilles les moins peuplées sont fusionnées
+    Octree,
+}
+
+// Implémentation de FromStr pour Enum
+impl FromStr for Quantificateur {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "median-cut" | "mediancut" => Ok(Quantificateur::MedianCut),
+            "kmeans" | "k-means" => Ok(Quantificateur::KMeans),
+            "wu" => Ok(Quantificateur::Wu),
+            "octree" => Ok(Quantificateur::Octree),
+            _ => Err(format!("Quantificateur invalide: {}", s)),
+        }
+    }
+}
+
+impl Quantificateur {
+    /// Générer une palette de n couleurs adaptée à l'image, les couleurs étant regroupées
+    /// dans l'espace donné. La graine n'est utilisée que par les k-moyennes.
+    pub fn generer_palette(
+        &self,
+        image_rgb8: &RgbImage,
+        n_couleurs: usize,
+        espace: EspaceCouleur,
+        graine: u64,
+    ) -> Palette {
+        let echantillons = echantillons(image_rgb8, espace);
+        if n_couleurs == 0 || echantillons.is_empty() {
+            return Palette::new(vec![]);
+        }
+
+        let centres = match self {
+            Quantificateur::MedianCut => median_cut(echantillons, n_couleurs),
+            Quantificateur::KMeans => k_moyennes(&echantillons, n_couleurs, graine),
+            Quantificateur::Wu => wu(&echantillons, n_couleurs),
+            Quantificateur::Octree => octree(&echantillons, n_couleurs),
+        };
+
+        let mut couleurs: Vec<Rgb<u8>> = vec![];
+        for centre in centres {
+            let couleur = espace.vers_srgb(centre);
+            // Deux centres proches peuvent donner la même couleur une fois arrondis
+            if !couleurs.contains(&couleur) {
+                couleurs.push(couleur);
+            }
+        }
+        Palette::new(couleurs)
+    }
+}
+
+/// Couleur distincte de l'image, exprimée dans l'espace de travail, avec son nombre d'occurrences
+#[derive(Debug, Clone, Copy)]
+struct Echantillon {
+    coordonnees: [f32; 3],
+    poids: f64,
+}
+
+/// Compter les occurrences de chaque couleur de l'image et les convertir dans l'espace de travail
+fn echantillons(image_rgb8: &RgbImage, espace: EspaceCouleur) -> Vec<Echantillon> {
+    let mut occurrences: HashMap<Rgb<u8>, u32> = HashMap::new();
+    for pixel in image_rgb8.pixels() {
+        *occurrences.entry(*pixel).or_insert(0) += 1;
+    }
+    let mut couleurs: Vec<(Rgb<u8>, u32)> = occurrences.into_iter().collect();
+    // Ordre déterministe, indépendant de l'itération de la table de hachage
+    couleurs.sort_by_key(|(couleur, _)| couleur.0);
+    couleurs
+        .into_iter()
+        .map(|(couleur, n)| Echantillon { coordonnees: espace.depuis_srgb(&couleur), poids: n as f64 })
+        .collect()
+}
+
+/// Moyenne des échantillons pondérée par leurs occurrences
+fn moyenne(echantillons: &[Echantillon]) -> [f32; 3] {
+    let mut somme = [0.0f64; 3];
+    let mut total = 0.0;
+    for echantillon in echantillons {
+        for (canal, valeur) in somme.iter_mut().enumerate() {
+            *valeur += echantillon.coordonnees[canal] as f64 * echantillon.poids;
+        }
+        total += echantillon.poids;
+    }
+    somme.map(|valeur| (valeur / total.max(f64::MIN_POSITIVE)) as f32)
+}
+
+/// Distance euclidienne au carré entre deux points de l'espace de travail
+fn distance_carree(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)
+}
+
+/// Ramener chaque axe sur une grille entière 0..=max à partir des bornes des échantillons
+fn discretiser(echantillons: &[Echantillon], max: f32) -> Vec<[usize; 3]> {
+    let mut bornes = [(f32::MAX, f32::MIN); 3];
+    for echantillon in echantillons {
+        for (canal, (min, maxi)) in bornes.iter_mut().enumerate() {
+            *min = min.min(echantillon.coordonnees[canal]);
+            *maxi = maxi.max(echantillon.coordonnees[canal]);
+        }
+    }
+    echantillons
+        .iter()
+        .map(|echantillon| {
+            let mut case = [0usize; 3];
+            for (canal, (min, maxi)) in bornes.iter().enumerate() {
+                let etendue = maxi - min;
+                if etendue > 0.0 {
+                    case[canal] = ((echantillon.coordonnees[canal] - min) / etendue * max).round() as usize;
+                }
+            }
+            case
+        })
+        .collect()
+}
+
+/// Median cut : on coupe la boîte la plus étendue à la médiane de son plus grand axe
+fn median_cut(echantillons: Vec<Echantillon>, n_couleurs: usize) -> Vec<[f32; 3]> {
+    /// Canal ayant la plus grande étendue, et cette étendue
+    fn plus_grande_etendue(boite: &[Echantillon]) -> (usize, f32) {
+        let mut meilleur = (0, 0.0);
+        for canal in 0..3 {
+            let valeurs = boite.iter().map(|e| e.coordonnees[canal]);
+            let min = valeurs.clone().fold(f32::MAX, f32::min);
+            let max = valeurs.fold(f32::MIN, f32::max);
+            if max - min > meilleur.1 {
+                meilleur = (canal, max - min);
+            }
+        }
+        meilleur
+    }
+
+    let mut boites = vec![echantillons];
+    while boites.len() < n_couleurs {
+        let candidate = boites
+            .iter()
+            .enumerate()
+            .filter(|(_, boite)| boite.len() > 1)
+            .map(|(i, boite)| (i, plus_grande_etendue(boite)))
+            .filter(|(_, (_, etendue))| *etendue > 0.0)
+            .max_by(|(_, (_, a)), (_, (_, b))| a.total_cmp(b));
+        let Some((indice, (canal, _))) = candidate else {
+            break; // l'image contient moins de couleurs que demandé
+        };
+
+        let mut boite = boites.swap_remove(indice);
+        boite.sort_by(|a, b| a.coordonnees[canal].total_cmp(&b.coordonnees[canal]));
+        // Médiane pondérée par les occurrences
+        let total: f64 = boite.iter().map(|e| e.poids).sum();
+        let mut cumul = 0.0;
+        let mut coupure = 1;
+        for (i, echantillon) in boite.iter().enumerate() {
+            cumul += echantillon.poids;
+            if cumul * 2.0 >= total {
+                coupure = i + 1;
+                break;
+            }
+        }
+        // Chaque moitié doit garder au moins une couleur
+        let coupure = coupure.clamp(1, boite.len() - 1);
+        let droite = boite.split_off(coupure);
+        boites.push(boite);
+        boites.push(droite);
+    }
+
+    boites.iter().map(|boite| moyenne(boite)).collect()
+}
+
+/// K-moyennes pondérées, initialisées par k-means++ avec une graine fixe
+fn k_moyennes(echantillons: &[Echantillon], n_couleurs: usize, graine: u64) -> Vec<[f32; 3]> {
+    const ITERATIONS_MAX: usize = 50;
+    let mut rng = StdRng::seed_from_u64(graine);
+    let k = n_couleurs.min(echantillons.len());
+
+    // k-means++ : chaque nouveau centre est tiré proportionnellement au carré
+    // de sa distance au centre existant le plus proche
+    let mut centres = vec![echantillons[rng.gen_range(0..echantillons.len())].coordonnees];
+    let mut distances: Vec<f32> = echantillons
+        .iter()
+        .map(|e| distance_carree(&e.coordonnees, &centres[0]))
+        .collect();
+    while centres.len() < k {
+        let total: f64 = echantillons.iter().zip(&distances).map(|(e, d)| e.poids * *d as f64).sum();
+        if total <= 0.0 {
+            break; // toutes les couleurs sont déjà des centres
+        }
+        let mut tirage = rng.gen::<f64>() * total;
+        let mut choisi = echantillons.len() - 1;
+        for (i, (e, d)) in echantillons.iter().zip(&distances).enumerate() {
+            tirage -= e.poids * *d as f64;
+            if tirage <= 0.0 && *d > 0.0 {
+                choisi = i;
+                break;
+            }
+        }
+        let centre = echantillons[choisi].coordonnees;
+        for (distance, e) in distances.iter_mut().zip(echantillons) {
+            *distance = distance.min(distance_carree(&e.coordonnees, &centre));
+        }
+        centres.push(centre);
+    }
+
+    // Itérations de Lloyd
+    let mut affectations = vec![usize::MAX; echantillons.len()];
+    for _ in 0..ITERATIONS_MAX {
+        let mut change = false;
+        for (affectation, e) in affectations.iter_mut().zip(echantillons) {
+            let plus_proche = (0..centres.len())
+                .min_by(|&a, &b| {
+                    distance_carree(&e.coordonnees, &centres[a])
+                        .total_cmp(&distance_carree(&e.coordonnees, &centres[b]))
+                })
+                .unwrap_or(0);
+            if plus_proche != *affectation {
+                change = true;
+                *affectation = plus_proche;
+            }
+        }
+        if !change {
+            break;
+        }
+
+        let mut groupes: Vec<Vec<Echantillon>> = vec![vec![]; centres.len()];
+        for (e, affectation) in echantillons.iter().zip(&affectations) {
+            groupes[*affectation].push(*e);
+        }
+        for (centre, groupe) in centres.iter_mut().zip(&groupes) {
+            if !groupe.is_empty() {
+                *centre = moyenne(groupe);
+            }
+        }
+    }
+    centres
+}
+
+/// Quantificateur de Xiaolin Wu : découpages successifs des boîtes d'un histogramme 3D
+/// cumulé, en choisissant à chaque fois la coupe qui réduit le plus la variance
+fn wu(echantillons: &[Echantillon], n_couleurs: usize) -> Vec<[f32; 3]> {
+    const TAILLE: usize = 33; // 32 cases par axe, plus une ligne de zéros pour les cumuls
+    let indice = |r: usize, g: usize, b: usize| (r * TAILLE + g) * TAILLE + b;
+
+    /// Moments de l'histogramme : poids, sommes des coordonnées et somme des carrés
+    #[derive(Clone, Copy, Default)]
+    struct Moments {
+        poids: f64,
+        somme: [f64; 3],
+        carres: f64,
+    }
+
+    impl Moments {
+        fn ajouter(&mut self, autre: &Moments, signe: f64) {
+            self.poids += signe * autre.poids;
+            for canal in 0..3 {
+                self.somme[canal] += signe * autre.somme[canal];
+            }
+            self.carres += signe * autre.carres;
+        }
+
+        fn variance(&self) -> f64 {
+            if self.poids <= 0.0 {
+                return 0.0;
+            }
+            let norme: f64 = self.somme.iter().map(|s| s * s).sum();
+            self.carres - norme / self.poids
+        }
+    }
+
+    // Boîte (min exclusif, max inclusif) sur chaque axe de la grille
+    #[derive(Clone, Copy)]
+    struct Boite {
+        min: [usize; 3],
+        max: [usize; 3],
+    }
+
+    // Histogramme puis cumul 3D
+    let cases = discretiser(echantillons, 31.0);
+    let mut moments = vec![Moments::default(); TAILLE * TAILLE * TAILLE];
+    for (echantillon, case) in echantillons.iter().zip(&cases) {
+        let m = &mut moments[indice(case[0] + 1, case[1] + 1, case[2] + 1)];
+        let c = echantillon.coordonnees.map(|v| v as f64);
+        m.poids += echantillon.poids;
+        for (somme, valeur) in m.somme.iter_mut().zip(c) {
+            *somme += valeur * echantillon.poids;
+        }
+        m.carres += (c[0] * c[0] + c[1] * c[1] + c[2] * c[2]) * echantillon.poids;
+    }
+    for r in 1..TAILLE {
+        for g in 1..TAILLE {
+            for b in 1..TAILLE {
+                let mut m = moments[indice(r, g, b)];
+                m.ajouter(&moments[indice(r - 1, g, b)], 1.0);
+                m.ajouter(&moments[indice(r, g - 1, b)], 1.0);
+                m.ajouter(&moments[indice(r, g, b - 1)], 1.0);
+                m.ajouter(&moments[indice(r - 1, g - 1, b)], -1.0);
+                m.ajouter(&moments[indice(r - 1, g, b - 1)], -1.0);
+                m.ajouter(&moments[indice(r, g - 1, b - 1)], -1.0);
+                m.ajouter(&moments[indice(r - 1, g - 1, b - 1)], 1.0);
+                moments[indice(r, g, b)] = m;
+            }
+        }
+    }
+
+    // Moments d'une boîte par inclusion-exclusion sur les cumuls
+    let volume = |boite: &Boite| {
+        let mut m = Moments::default();
+        for (coin, signe) in [
+            ([1, 1, 1], 1.0),
+            ([1, 1, 0], -1.0),
+            ([1, 0, 1], -1.0),
+            ([1, 0, 0], 1.0),
+            ([0, 1, 1], -1.0),
+            ([0, 1, 0], 1.0),
+            ([0, 0, 1], 1.0),
+            ([0, 0, 0], -1.0),
+        ] {
+            let choisir = |canal: usize| if coin[canal] == 1 { boite.max[canal] } else { boite.min[canal] };
+            m.ajouter(&moments[indice(choisir(0), choisir(1), choisir(2))], signe);
+        }
+        m
+    };
+
+    // Meilleure coupe d'une boîte : celle qui maximise la somme des |somme|²/poids des deux moitiés
+    let couper = |boite: &Boite| -> Option<(Boite, Boite)> {
+        let total = volume(boite);
+        let mut meilleure: Option<(f64, Boite, Boite)> = None;
+        for canal in 0..3 {
+            for position in boite.min[canal] + 1..boite.max[canal] {
+                let mut moitie = *boite;
+                moitie.max[canal] = position;
+                let bas = volume(&moitie);
+                let mut haut = total;
+                haut.ajouter(&bas, -1.0);
+                if bas.poids <= 0.0 || haut.poids <= 0.0 {
+                    continue;
+                }
+                let score = bas.somme.iter().map(|s| s * s).sum::<f64>() / bas.poids
+                    + haut.somme.iter().map(|s| s * s).sum::<f64>() / haut.poids;
+                if meilleure.as_ref().is_none_or(|(meilleur_score, _, _)| score > *meilleur_score) {
+                    let mut autre = *boite;
+                    autre.min[canal] = position;
+                    meilleure = Some((score, moitie, autre));
+                }
+            }
+        }
+        meilleure.map(|(_, a, b)| (a, b))
+    };
+
+    let mut boites = vec![Boite { min: [0; 3], max: [TAILLE - 1; 3] }];
+    let mut variances = vec![volume(&boites[0]).variance()];
+    while boites.len() < n_couleurs {
+        // On coupe la boîte de plus grande variance encore divisible
+        let Some(suivante) = (0..boites.len())
+            .filter(|&i| variances[i] > 0.0)
+            .max_by(|&a, &b| variances[a].total_cmp(&variances[b]))
+        else {
+            break;
+        };
+        match couper(&boites[suivante]) {
+            Some((a, b)) => {
+                boites[suivante] = a;
+                variances[suivante] = volume(&a).variance();
+                boites.push(b);
+                variances.push(volume(&b).variance());
+            }
+            None => variances[suivante] = 0.0, // boîte réduite à une seule case
+        }
+    }
+
+    boites
+        .iter()
+        .map(volume)
+        .filter(|m| m.poids > 0.0)
+        .map(|m| m.somme.map(|s| (s / m.poids) as f32))
+        .collect()
+}
+
+/// Arbre octal sur les coordonnées ramenées à 8 bits par axe : on fusionne les nœuds
+/// les plus profonds et les moins peuplés jusqu'à ne plus avoir que n feuilles
+fn octree(echantillons: &[Echantillon], n_couleurs: usize) -> Vec<[f32; 3]> {
+    const PROFONDEUR: usize = 8;
+
+    #[derive(Clone, Default)]
+    struct Noeud {
+        enfants: [Option<usize>; 8],
+        somme: [f64; 3],
+        poids: f64,
+        feuille: bool,
+    }
+
+    let cases = discretiser(echantillons, 255.0);
+    let mut noeuds = vec![Noeud::default()];
+    // Nœuds internes de chaque niveau, candidats à la fusion
+    let mut niveaux: Vec<Vec<usize>> = vec![vec![]; PROFONDEUR];
+    let mut feuilles = 0;
+
+    for (echantillon, case) in echantillons.iter().zip(&cases) {
+        let mut courant = 0;
+        for (niveau, noeuds_niveau) in niveaux.iter_mut().enumerate() {
+            let bit = 7 - niveau;
+            let enfant = ((case[0] >> bit) & 1) << 2 | ((case[1] >> bit) & 1) << 1 | ((case[2] >> bit) & 1);
+            if noeuds[courant].enfants.iter().all(Option::is_none) {
+                noeuds_niveau.push(courant);
+            }
+            courant = match noeuds[courant].enfants[enfant] {
+                Some(suivant) => suivant,
+                None => {
+                    noeuds.push(Noeud { feuille: niveau + 1 == PROFONDEUR, ..Noeud::default() });
+                    let nouveau = noeuds.len() - 1;
+                    noeuds[courant].enfants[enfant] = Some(nouveau);
+                    if niveau + 1 == PROFONDEUR {
+                        feuilles += 1;
+                    }
+                    nouveau
+                }
+            };
+        }
+        let feuille = &mut noeuds[courant];
+        for canal in 0..3 {
+            feuille.somme[canal] += echantillon.coordonnees[canal] as f64 * echantillon.poids;
+        }
+        feuille.poids += echantillon.poids;
+    }
+
+    // Les poids des nœuds internes servent à choisir les nœuds à fusionner
+    fn poids_sous_arbre(noeuds: &mut [Noeud], indice: usize) -> f64 {
+        if noeuds[indice].feuille {
+            return noeuds[indice].poids;
+        }
+        let enfants = noeuds[indice].enfants;
+        let poids = enfants.iter().flatten().map(|&enfant| poids_sous_arbre(noeuds, enfant)).sum();
+        noeuds[indice].poids = poids;
+        poids
+    }
+    poids_sous_arbre(&mut noeuds, 0);
+    // Les poids ne changent plus : chaque niveau est trié une fois pour fusionner
+    // en premier les nœuds les moins peuplés (en fin de liste)
+    for noeuds_niveau in niveaux.iter_mut() {
+        noeuds_niveau.sort_by(|&a, &b| noeuds[b].poids.total_cmp(&noeuds[a].poids));
+    }
+
+    while feuilles > n_couleurs {
+        let Some(indice) = niveaux.iter_mut().rev().find_map(|noeuds_niveau| noeuds_niveau.pop()) else {
+            break;
+        };
+
+        // Fusion des feuilles enfants dans le nœud
+        let enfants = std::mem::take(&mut noeuds[indice].enfants);
+        let mut somme = [0.0; 3];
+        for &enfant in enfants.iter().flatten() {
+            for (canal, valeur) in somme.iter_mut().enumerate() {
+                *valeur += noeuds[enfant].somme[canal];
+            }
+            noeuds[enfant].feuille = false; // l'enfant n'est plus référencé par l'arbre
+            feuilles -= 1;
+        }
+        noeuds[indice].somme = somme;
+        noeuds[indice].feuille = true;
+        feuilles += 1;
+    }
+
+    noeuds
+        .iter()
+        .filter(|noeud| noeud.feuille && noeud.poids > 0.0)
+        .map(|noeud| noeud.somme.map(|s| (s / noeud.poids) as f32))
+        .collect()
+}
@@ -0,0 +1,131 @@
+use std::str::FromStr;
+
+/// Écart type, en pixels, du filtre gaussien modélisant le flou de l'œil
+const SIGMA_VISION: f32 = 1.5;
+
+/// Tramage servant de point de départ à la recherche binaire directe
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum EtatInitialDbs {
+    /// Bruit blanc, le plus éloigné du résultat : la recherche fait alors tout le travail
+    Aleatoire,
+    /// Matrice de Bayer 8x8
+    Bayer,
+    /// Diffusion d'erreur de Floyd-Steinberg, déjà proche d'un optimum
+    #[default]
+    FloydSteinberg,
+}
+
+// Implémentation de FromStr pour Enum
+impl FromStr for EtatInitialDbs {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "aleatoire" => Ok(EtatInitialDbs::Aleatoire),
+            "bayer" | "ordonne" => Ok(EtatInitialDbs::Bayer),
+            "floyd-steinberg" | "floydsteinberg" => Ok(EtatInitialDbs::FloydSteinberg),
+            _ => Err(format!("État initial invalide: {}", s)),
+        }
+    }
+}
+
+/// Autocorrélation du filtre de vision : une gaussienne d'écart type `SIGMA_VISION * √2`,
+/// stockée sur un carré de côté `2 * rayon + 1` et valant 1 au centre
+fn autocorrelation_vision() -> (Vec<f32>, i32) {
+    let sigma = SIGMA_VISION * std::f32::consts::SQRT_2;
+    let rayon = (3.0 * sigma).ceil() as i32;
+    let cote = 2 * rayon + 1;
+    let mut filtre = Vec::with_capacity((cote * cote) as usize);
+    for dy in -rayon..=rayon {
+        for dx in -rayon..=rayon {
+            filtre.push((-((dx * dx + dy * dy) as f32) / (2.0 * sigma * sigma)).exp());
+        }
+    }
+    (filtre, rayon)
+}
+
+/// Recherche binaire directe (DBS, Analoui et Allebach) : à partir d'un tramage initial, chaque pixel
+/// est tour à tour inversé ou échangé avec l'un de ses 8 voisins de valeur opposée lorsque cela réduit
+/// l'écart quadratique entre l'image et le tramage, tous deux vus à travers le filtre de vision.
+/// `cible` contient les luminosités entre 0 et 1 et `etat` le tramage (`true` pour un pixel clair),
+/// modifié sur place. Les passes s'arrêtent lorsqu'aucun pixel ne change ou après `passes_max`
+/// passes ; le nombre de passes effectuées est renvoyé
+pub fn recherche_binaire_directe(cible: &[f32], etat: &mut [bool], largeur: usize, hauteur: usize, passes_max: usize) -> usize {
+    let (filtre, rayon) = autocorrelation_vision();
+    let cote = (2 * rayon + 1) as usize;
+    let centre = filtre[rayon as usize * cote + rayon as usize];
+    let (largeur_i, hauteur_i) = (largeur as i32, hauteur as i32);
+
+    // Ajouter `amplitude` fois le filtre centré sur (x, y) à la corrélation croisée
+    let propager = |correlation: &mut [f32], x: i32, y: i32, amplitude: f32| {
+        for dy in -rayon..=rayon {
+            let vy = y + dy;
+            if vy < 0 || vy >= hauteur_i {
+                continue;
+            }
+            for dx in -rayon..=rayon {
+                let vx = x + dx;
+                if vx < 0 || vx >= largeur_i {
+                    continue;
+                }
+                correlation[(vy * largeur_i + vx) as usize] += amplitude * filtre[((dy + rayon) as usize) * cote + (dx + rayon) as usize];
+            }
+        }
+    };
+
+    // Corrélation croisée entre l'erreur (tramage - cible) et l'autocorrélation du filtre
+    let mut correlation = vec![0.0f32; largeur * hauteur];
+    for y in 0..hauteur_i {
+        for x in 0..largeur_i {
+            let indice = (y * largeur_i + x) as usize;
+            let erreur = etat[indice] as u8 as f32 - cible[indice];
+            if erreur != 0.0 {
+                propager(&mut correlation, x, y, erreur);
+            }
+        }
+    }
+
+    for passe in 0..passes_max {
+        let mut changements = 0;
+        for y in 0..hauteur_i {
+            for x in 0..largeur_i {
+                let indice = (y * largeur_i + x) as usize;
+                let a0 = if etat[indice] { -1.0 } else { 1.0 };
+                // Variation de l'erreur perçue pour une inversion du pixel
+                let mut meilleure_variation = 2.0 * a0 * correlation[indice] + centre;
+                let mut meilleur_voisin = None;
+                for (dx, dy) in [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)] {
+                    let (vx, vy) = (x + dx, y + dy);
+                    if vx < 0 || vy < 0 || vx >= largeur_i || vy >= hauteur_i {
+                        continue;
+                    }
+                    let voisin = (vy * largeur_i + vx) as usize;
+                    if etat[voisin] == etat[indice] {
+                        continue;
+                    }
+                    // Échange : le voisin change dans le sens opposé (a1 = -a0)
+                    let croise = filtre[((dy + rayon) as usize) * cote + (dx + rayon) as usize];
+                    let variation = 2.0 * a0 * (correlation[indice] - correlation[voisin]) + 2.0 * centre - 2.0 * croise;
+                    if variation < meilleure_variation {
+                        meilleure_variation = variation;
+                        meilleur_voisin = Some((vx, vy, voisin));
+                    }
+                }
+                if meilleure_variation >= -1e-6 {
+                    continue;
+                }
+                etat[indice] = !etat[indice];
+                propager(&mut correlation, x, y, a0);
+                if let Some((vx, vy, voisin)) = meilleur_voisin {
+                    etat[voisin] = !etat[voisin];
+                    propager(&mut correlation, vx, vy, -a0);
+                }
+                changements += 1;
+            }
+        }
+        if changements == 0 {
+            return passe + 1;
+        }
+    }
+    passes_max
+}
@@ -0,0 +1,153 @@
+use std::str::FromStr;
+
+/// Nombre d'échantillons par côté de cellule pour calibrer les fonctions de point
+const ECHANTILLONS_CALIBRAGE: usize = 64;
+
+/// Forme des points d'une trame d'impression (fonction de point PostScript)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FonctionPoint {
+    /// Point rond qui devient un trou rond au-delà de 50 % (point euclidien)
+    #[default]
+    Rond,
+    /// Point elliptique, dont les chaînes se rejoignent d'abord dans une direction
+    Elliptique,
+    /// Lignes parallèles
+    Ligne,
+    /// Point carré
+    Carre,
+    /// Point en losange
+    Losange,
+}
+
+// Implémentation de FromStr pour Enum
+impl FromStr for FonctionPoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "rond" | "round" => Ok(FonctionPoint::Rond),
+            "elliptique" | "elliptical" => Ok(FonctionPoint::Elliptique),
+            "ligne" | "line" => Ok(FonctionPoint::Ligne),
+            "carre" | "square" => Ok(FonctionPoint::Carre),
+            "losange" | "diamond" => Ok(FonctionPoint::Losange),
+            _ => Err(format!("Fonction de point invalide: {}", s)),
+        }
+    }
+}
+
+impl FonctionPoint {
+    /// Valeur de la fonction en un point (u, v) de la cellule, avec u et v entre -1 et 1 :
+    /// plus la valeur est faible, plus le point est encré tôt
+    fn valeur(&self, u: f32, v: f32) -> f32 {
+        let (au, av) = (u.abs(), v.abs());
+        match self {
+            FonctionPoint::Rond => {
+                if au + av <= 1.0 {
+                    u * u + v * v
+                } else {
+                    // Au-delà de la diagonale, on mesure la distance au coin le plus proche
+                    4.0 - (1.0 - au).powi(2) - (1.0 - av).powi(2)
+                }
+            }
+            FonctionPoint::Elliptique => {
+                // Fonction elliptique d'Adobe, de signe inversé
+                let w = 3.0 * au + 4.0 * av - 3.0;
+                if w < 0.0 {
+                    (au * au + (av / 0.75).powi(2)) / 4.0 - 1.0
+                } else if w > 1.0 {
+                    1.0 - ((1.0 - au).powi(2) + ((1.0 - av) / 0.75).powi(2)) / 4.0
+                } else {
+                    w - 0.5
+                }
+            }
+            FonctionPoint::Ligne => av,
+            FonctionPoint::Carre => au.max(av),
+            FonctionPoint::Losange => au + av,
+        }
+    }
+}
+
+/// Trame d'impression à points groupés : grille de cellules tournée de l'angle de trame,
+/// évaluée en chaque pixel sur la grille continue plutôt que par répétition d'une tuile
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trame {
+    fonction: FonctionPoint,
+    /// Taille d'une cellule en pixels
+    periode: f32,
+    cosinus: f32,
+    sinus: f32,
+    /// Valeurs distinctes de la fonction de point sur une cellule, triées, avec la fraction de la
+    /// cellule dont la valeur est inférieure : la surface encrée est ainsi proportionnelle au ton
+    repartition: Vec<(f32, f32)>,
+}
+
+impl Trame {
+    /// Créer une trame de `lpi` lignes par pouce pour une image de `dpi` pixels par pouce,
+    /// tournée de `angle` degrés
+    pub fn new(fonction: FonctionPoint, lpi: f32, dpi: f32, angle: f32) -> Self {
+        let mut valeurs = Vec::with_capacity(ECHANTILLONS_CALIBRAGE * ECHANTILLONS_CALIBRAGE);
+        for i in 0..ECHANTILLONS_CALIBRAGE {
+            for j in 0..ECHANTILLONS_CALIBRAGE {
+                let u = (i as f32 + 0.5) / ECHANTILLONS_CALIBRAGE as f32 * 2.0 - 1.0;
+                let v = (j as f32 + 0.5) / ECHANTILLONS_CALIBRAGE as f32 * 2.0 - 1.0;
+                valeurs.push(fonction.valeur(u, v));
+            }
+        }
+        valeurs.sort_by(f32::total_cmp);
+        // Les valeurs égales (fréquentes pour les lignes) prennent le rang médian de leur groupe
+        let total = valeurs.len() as f32;
+        let mut repartition = Vec::new();
+        let mut debut = 0;
+        while debut < valeurs.len() {
+            let fin = debut + valeurs[debut..].partition_point(|&v| v <= valeurs[debut]);
+            repartition.push((valeurs[debut], (debut + fin) as f32 / 2.0 / total));
+            debut = fin;
+        }
+        let (sinus, cosinus) = angle.to_radians().sin_cos();
+        Trame { fonction, periode: dpi / lpi, cosinus, sinus, repartition }
+    }
+
+    /// Seuil entre 0 et 1 au pixel (x, y) : le pixel est encré si sa densité dépasse ce seuil
+    pub fn seuil(&self, x: u32, y: u32) -> f32 {
+        let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+        // Coordonnées dans le repère de la trame, en cellules
+        let u = (px * self.cosinus + py * self.sinus) / self.periode;
+        let v = (-px * self.sinus + py * self.cosinus) / self.periode;
+        let u = (u - u.floor()) * 2.0 - 1.0;
+        let v = (v - v.floor()) * 2.0 - 1.0;
+        let valeur = self.fonction.valeur(u, v);
+        // Interpolation linéaire entre les deux valeurs calibrées qui encadrent celle du pixel
+        let indice = self.repartition.partition_point(|&(v, _)| v < valeur);
+        if indice == 0 {
+            return self.repartition[0].1;
+        }
+        let Some(&(valeur_haute, rang_haut)) = self.repartition.get(indice) else {
+            return self.repartition[indice - 1].1;
+        };
+        let (valeur_basse, rang_bas) = self.repartition[indice - 1];
+        rang_bas + (rang_haut - rang_bas) * (valeur - valeur_basse) / (valeur_haute - valeur_basse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fonction_elliptique_valeurs_de_reference() {
+        let elliptique = FonctionPoint::Elliptique;
+        assert_eq!(elliptique.valeur(0.0, 0.0), -1.0);
+        assert_eq!(elliptique.valeur(1.0, 1.0), 1.0);
+        assert_eq!(elliptique.valeur(0.5, 0.5), 0.0);
+        assert!((elliptique.valeur(0.5, -0.25) - (0.25 + 1.0 / 9.0) / 4.0 + 1.0).abs() < 1e-6);
+        assert!((elliptique.valeur(-0.9, 0.9) - 1.0 + (0.01 + 0.1f32.powi(2) / 0.5625) / 4.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn fonction_elliptique_atteint_les_coins_du_losange() {
+        // Le plus gros point touche les deux sommets du losange w = 0, en (1, 0) et (0, 0.75)
+        let elliptique = FonctionPoint::Elliptique;
+        let (horizontal, vertical) = (elliptique.valeur(0.9999, 0.0), elliptique.valeur(0.0, 0.7499));
+        assert!((horizontal + 0.75).abs() < 1e-3 && (vertical + 0.75).abs() < 1e-3);
+    }
+}
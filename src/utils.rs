@@ -1,48 +1,55 @@
+use std::collections::VecDeque;
+
 use image::io::Reader as ImageReader;
-use image::{Pixel, Rgb, RgbImage};
+use image::{GrayImage, ImageError, Rgb, RgbImage};
 use rand::Rng;
 
+use crate::erreur::DitherError;
+use crate::espaces_couleur;
+use crate::metrique::MetriqueCouleur;
+
 
 /// Lit une image à partir d'un chemin et la convertit en mode RGB8
-pub fn charger_image_rgb8(path: &str) -> RgbImage {
-    match ImageReader::open(path) {
-        Ok(reader) => match reader.decode() {
-            Ok(img) => img.to_rgb8(),
-            Err(err) => {
-                eprintln!("Erreur lors de la conversion de l'image : {}", err);
-                std::process::exit(1); // Quitte le programme avec un code d'erreur
-                
-                // Return a default RgbImage to satisfy the return type
-            }
-        },
-        Err(err) => {
-            eprintln!("Erreur lors de l'ouverture du fichier : {}", err);
-            std::process::exit(1); // Quitte le programme avec un code d'erreur
+pub fn charger_image_rgb8(path: &str) -> Result<RgbImage, DitherError> {
+    let reader = ImageReader::open(path)?;
+    let img = reader.decode().map_err(DitherError::Decodage)?;
+    Ok(img.to_rgb8())
+}
 
-        }
-    }
+/// Charger une carte de seuils depuis une image quelconque convertie en niveaux de gris,
+/// sous forme de matrice `matrice[x][y]` de valeurs entre 0 et 255
+pub fn charger_carte_seuil(path: &str) -> Result<Vec<Vec<f32>>, DitherError> {
+    let reader = ImageReader::open(path)?;
+    let carte = reader.decode().map_err(DitherError::Decodage)?.to_luma8();
+    Ok((0..carte.width())
+        .map(|x| (0..carte.height()).map(|y| carte.get_pixel(x, y)[0] as f32).collect())
+        .collect())
 }
 
 /// Sauvegarder une image RGB8 dans un fichier au format PNG
-pub fn sauvegarder_image_rgb8(image_rgb8: &RgbImage, path_out: &str) {
-    match image_rgb8.save(path_out) {
-        Ok(_) => println!("Image sauvegardée avec succès à l'emplacement : {}", path_out),
-        Err(err) => {
-            eprintln!("Erreur lors de la sauvegarde de l'image : {}", err);
-            std::process::exit(1); // Quitte le programme avec un code d'erreur
-        }
-    }
+pub fn sauvegarder_image_rgb8(image_rgb8: &RgbImage, path_out: &str) -> Result<(), DitherError> {
+    image_rgb8.save(path_out).map_err(|err| match err {
+        ImageError::IoError(err) => DitherError::Io(err),
+        err => DitherError::Encodage(err),
+    })
+}
+
+/// Sauvegarder une image en niveaux de gris (une extension .pbm donne un fichier 1 bit)
+pub fn sauvegarder_image_gris(image_gris: &GrayImage, path_out: &str) -> Result<(), DitherError> {
+    image_gris.save(path_out).map_err(|err| match err {
+        ImageError::IoError(err) => DitherError::Io(err),
+        err => DitherError::Encodage(err),
+    })
 }
 
 /// Récupérer un pixel à partir de ses coordonnées
-pub fn recuperer_pixel(image_rgb8: &RgbImage, x: u32, y: u32) -> Rgb<u8> {
+pub fn recuperer_pixel(image_rgb8: &RgbImage, x: u32, y: u32) -> Result<Rgb<u8>, DitherError> {
     // Vérifie si les coordonnées sont valides
     if x < image_rgb8.width() && y < image_rgb8.height() {
-        *image_rgb8.get_pixel(x, y)
+        Ok(*image_rgb8.get_pixel(x, y))
     } 
     else {
-        eprintln!("Erreur de récupération du pixel car les coordonnées sont hors des limites de l'image");
-        std::process::exit(1); // Quitte le programme avec un code d'erreur
+        Err(DitherError::HorsLimites { x, y, largeur: image_rgb8.width(), hauteur: image_rgb8.height() })
     }
 }
 
@@ -92,25 +99,24 @@ pub fn creer_liste_couleurs() -> Vec<(&'static str, Rgb<u8>)> {
 }
 
 /// Obtenir une couleur par son nom à partir de la liste
-pub fn obtenir_couleur_par_nom(nom: &str, liste_couleurs: &Vec<(&'static str, Rgb<u8>)>) -> Rgb<u8> {
+pub fn obtenir_couleur_par_nom(nom: &str, liste_couleurs: &[(&'static str, Rgb<u8>)]) -> Result<Rgb<u8>, DitherError> {
     for (nom_couleur, rgb) in liste_couleurs {
         if *nom_couleur == nom {
-            return *rgb;
+            return Ok(*rgb);
         }
     }
-    eprintln!("Erreur : La couleur '{}' n'est pas disponible.", nom);
-    std::process::exit(1); // Quitte le programme avec un code d'erreur
+    Err(DitherError::CouleurInconnue(nom.to_string()))
 }
 
 // Fonction de seuillage monochrome
-pub fn monochrome_par_seuillage(image_rgb8: &mut RgbImage, couleur_1: Rgb<u8>, couleur_2: Rgb<u8>) {
+pub fn monochrome_par_seuillage(image_rgb8: &mut RgbImage, seuil: f32, couleur_1: Rgb<u8>, couleur_2: Rgb<u8>) {
     // Parcourir tous les pixels de l'image
     for (_x, _y, pixel) in image_rgb8.enumerate_pixels_mut() {
         // Calculer la luminosité du pixel
         let luminosite = luminosite_pixel(pixel);
         
-        // Si la luminosité est supérieure à 128 (seuillage à 50%), appliquer couleur_1, sinon couleur_2
-        if luminosite > 128.0 {
+        // Si la luminosité est supérieure au seuil, appliquer couleur_1, sinon couleur_2
+        if luminosite > seuil {
             *pixel = couleur_1;
         }
         else {
@@ -119,6 +125,67 @@ pub fn monochrome_par_seuillage(image_rgb8: &mut RgbImage, couleur_1: Rgb<u8>, c
     }
 }
 
+/// Histogramme des luminosités de l'image : la case `n` compte les luminosités comprises dans
+/// l'intervalle ]n - 0.5, n + 0.5], de sorte qu'un pixel est plus clair que `n + 0.5` exactement
+/// quand sa case est au-delà de `n`
+pub fn histogramme_luminosite(image_rgb8: &RgbImage) -> [u64; 256] {
+    let mut histogramme = [0u64; 256];
+    for pixel in image_rgb8.pixels() {
+        histogramme[(luminosite_pixel(pixel) - 0.5).ceil().clamp(0.0, 255.0) as usize] += 1;
+    }
+    histogramme
+}
+
+/// Seuil d'Otsu : le niveau qui sépare l'histogramme en deux classes (luminosité inférieure ou égale
+/// au niveau, et supérieure) de variance interclasse maximale. Le seuil renvoyé est la borne haute
+/// de la case de ce niveau (`niveau + 0.5`), à comparer aux luminosités non arrondies
+pub fn seuil_otsu(histogramme: &[u64; 256]) -> f32 {
+    let total: u64 = histogramme.iter().sum();
+    let somme_totale: f64 = histogramme.iter().enumerate().map(|(niveau, &n)| niveau as f64 * n as f64).sum();
+    let (mut poids_sombre, mut somme_sombre) = (0u64, 0.0f64);
+    let (mut variance_max, mut meilleur) = (-1.0f64, 128);
+    for (niveau, &n) in histogramme.iter().enumerate() {
+        poids_sombre += n;
+        somme_sombre += niveau as f64 * n as f64;
+        let poids_clair = total - poids_sombre;
+        if poids_sombre == 0 || poids_clair == 0 {
+            continue;
+        }
+        let moyenne_sombre = somme_sombre / poids_sombre as f64;
+        let moyenne_claire = (somme_totale - somme_sombre) / poids_clair as f64;
+        let variance = poids_sombre as f64 * poids_clair as f64 * (moyenne_sombre - moyenne_claire).powi(2);
+        if variance > variance_max {
+            variance_max = variance;
+            meilleur = niveau;
+        }
+    }
+    meilleur as f32 + 0.5
+}
+
+/// Luminosité moyenne de l'image, calculée sur les luminosités non arrondies
+pub fn luminosite_moyenne(image_rgb8: &RgbImage) -> f32 {
+    let nombre = image_rgb8.pixels().len();
+    if nombre == 0 {
+        return 128.0;
+    }
+    let somme: f64 = image_rgb8.pixels().map(|pixel| luminosite_pixel(pixel) as f64).sum();
+    (somme / nombre as f64) as f32
+}
+
+/// Luminosité médiane d'un histogramme : la borne haute de la case du premier niveau atteint par
+/// la moitié des pixels
+pub fn seuil_median(histogramme: &[u64; 256]) -> f32 {
+    let total: u64 = histogramme.iter().sum();
+    let mut cumul = 0;
+    for (niveau, &n) in histogramme.iter().enumerate() {
+        cumul += n;
+        if cumul * 2 >= total && cumul > 0 {
+            return niveau as f32 + 0.5;
+        }
+    }
+    128.0
+}
+
 /// Calculer la distance euclidienne entre deux couleurs RGB
 pub fn distance_couleurs(couleur1: &Rgb<u8>, couleur2: &Rgb<u8>) -> f32 {
     let r_diff = couleur1[0] as f32 - couleur2[0] as f32;
@@ -129,13 +196,16 @@ pub fn distance_couleurs(couleur1: &Rgb<u8>, couleur2: &Rgb<u8>) -> f32 {
 }
 
 // Fonction de palette monochrome
-pub fn monochrome_par_palette(image_rgb8: &mut RgbImage, couleurs_palette: Vec<Rgb<u8>>) {
+pub fn monochrome_par_palette(image_rgb8: &mut RgbImage, couleurs_palette: &[Rgb<u8>], metrique: MetriqueCouleur) {
+    // Coordonnées de la palette calculées une seule fois pour toute l'image
+    let coordonnees_palette: Vec<[f32; 3]> = couleurs_palette.iter().map(|c| metrique.coordonnees(c)).collect();
     // Parcourir tous les pixels de l'image
     for (_x, _y, pixel) in image_rgb8.enumerate_pixels_mut() {
-        let mut distance_min = std::f32::MAX;
+        let coordonnees_pixel = metrique.coordonnees(pixel);
+        let mut distance_min = f32::MAX;
         let mut couleur_plus_proche = *pixel;
-        for couleur in &couleurs_palette {
-            let distance = distance_couleurs(pixel, couleur);
+        for (couleur, coordonnees) in couleurs_palette.iter().zip(&coordonnees_palette) {
+            let distance = metrique.distance_coordonnees(&coordonnees_pixel, coordonnees);
             if distance < distance_min {
                 distance_min = distance;
                 couleur_plus_proche = *couleur;
@@ -146,21 +216,21 @@ pub fn monochrome_par_palette(image_rgb8: &mut RgbImage, couleurs_palette: Vec<R
     }
 }
 
-/// Appliquer un tramage aléatoire sur une image RGB8
-pub fn tramage_aleatoire(image_rgb8: &mut RgbImage) {
+/// Appliquer un tramage aléatoire sur une image RGB8 avec une paire de couleurs
+pub fn tramage_aleatoire(image_rgb8: &mut RgbImage, couleur_1: Rgb<u8>, couleur_2: Rgb<u8>) {
     for (_x, _y, pixel) in image_rgb8.enumerate_pixels_mut() {
         let luminosite = luminosite_pixel(pixel);
         let seuil: f32 = rand::thread_rng().gen();
         if luminosite / 255.0 > seuil {
-            *pixel = Rgb([255, 255, 255]);
+            *pixel = couleur_1;
         }
         else {
-            *pixel = Rgb([0, 0, 0]);
+            *pixel = couleur_2;
         }
     }
 }
 
-/// Générer une matrice de Bayer de taille 2^order
+/// Générer une matrice de Bayer de taille 2^order, sans limite d'ordre (voir `TramageOrdonne::bayer`)
 pub fn generer_matrice_bayer(order: u32) -> Vec<Vec<f32>> {
     if order == 0 {
         return vec![vec![0.0]];
@@ -185,7 +255,7 @@ pub fn generer_matrice_bayer(order: u32) -> Vec<Vec<f32>> {
 }
 
 /// Afficher une matrice d'entiers
-pub fn afficher_matrice(matrice: &Vec<Vec<f32>>) {
+pub fn afficher_matrice(matrice: &[Vec<f32>]) {
     for ligne in matrice {
         for valeur in ligne {
             print!("{} ", valeur);
@@ -194,27 +264,31 @@ pub fn afficher_matrice(matrice: &Vec<Vec<f32>>) {
     }
 }
 
-/// Appliquer un tramage ordonné sur une image RGB8 en utilisant une matrice de Bayer
-pub fn tramage_ordonne(image_rgb8: &mut RgbImage, matrice_bayer: &Vec<Vec<f32>>) {
-    let taille = matrice_bayer.len();
+/// Appliquer un tramage ordonné sur une image RGB8 en répétant une matrice de seuils (indexée par
+/// `matrice[x][y]`) dont les valeurs, divisées par `niveaux`, sont comprises entre 0 et 1
+pub fn tramage_ordonne(image_rgb8: &mut RgbImage, matrice_bayer: &[Vec<f32>], niveaux: f32, couleur_1: Rgb<u8>, couleur_2: Rgb<u8>) {
+    let largeur = matrice_bayer.len();
+    let hauteur = matrice_bayer[0].len();
     for (x, y, pixel) in image_rgb8.enumerate_pixels_mut() {
         let luminosite = luminosite_pixel(pixel);
-        let i = x as usize % taille;
-        let j = y as usize % taille;
-        if luminosite / 255.0 > matrice_bayer[i][j] as f32 / (taille * taille) as f32 {
-            *pixel = Rgb([255, 255, 255]);
+        let i = x as usize % largeur;
+        let j = y as usize % hauteur;
+        if luminosite / 255.0 > matrice_bayer[i][j] / niveaux {
+            *pixel = couleur_1;
         }
         else {
-            *pixel = Rgb([0, 0, 0]);
+            *pixel = couleur_2;
         }
     }
 }
 
-fn couleur_la_plus_proche(pixel: &Rgb<u8>, couleurs_palette: &Vec<Rgb<u8>>) -> Rgb<u8>{
-    let mut distance_min = std::f32::MAX;
+/// Trouver la couleur de la palette la plus proche d'un pixel selon la métrique donnée
+pub fn couleur_la_plus_proche(pixel: &Rgb<u8>, couleurs_palette: &[Rgb<u8>], metrique: MetriqueCouleur) -> Rgb<u8>{
+    let coordonnees_pixel = metrique.coordonnees(pixel);
+    let mut distance_min = f32::MAX;
     let mut couleur_plus_proche = *pixel;
     for couleur in couleurs_palette {
-        let distance = distance_couleurs(pixel, couleur);
+        let distance = metrique.distance_coordonnees(&coordonnees_pixel, &metrique.coordonnees(couleur));
         if distance < distance_min {
             distance_min = distance;
             couleur_plus_proche = *couleur;
@@ -223,75 +297,244 @@ fn couleur_la_plus_proche(pixel: &Rgb<u8>, couleurs_palette: &Vec<Rgb<u8>>) -> R
     couleur_plus_proche
 }
 
+/// Diffusion d'erreur noir et blanc : la moitié de l'erreur va au pixel de droite, l'autre moitié au pixel du dessous
 pub fn diffusion_erreur(image_rgb8: &mut RgbImage){
+    diffusion_erreur_generique(
+        image_rgb8,
+        &[Rgb([0, 0, 0]), Rgb([255, 255, 255])],
+        &simple_2_d(),
+        (1, 1),
+        MetriqueCouleur::Euclidienne,
+        false,
+    );
+}
+
+/// Moteur commun des diffusions d'erreur : l'erreur est accumulée dans un tampon f32 couvrant
+/// toute l'image, sans écrêtage, et les pixels ne sont quantifiés qu'à l'écriture du résultat.
+/// `noyau(x, y)` donne la matrice de diffusion du pixel (x, y), ce qui permet des coefficients
+/// variables d'un pixel à l'autre, et `quantifier(x, y, valeur)` reçoit la valeur accumulée d'un
+/// pixel et renvoie la couleur à écrire ainsi que sa valeur dans l'espace du tampon. `origine`
+/// (ligne, colonne) désigne la case des matrices correspondant au pixel courant. En balayage
+/// serpentin, les lignes impaires sont parcourues de droite à gauche avec les matrices retournées
+/// horizontalement
+pub(crate) fn diffuser_erreur<'a, N, F>(
+    image_rgb8: &mut RgbImage,
+    mut tampon: Vec<[f32; 3]>,
+    origine: (usize, usize),
+    serpentin: bool,
+    mut noyau: N,
+    mut quantifier: F,
+) where
+    N: FnMut(u32, u32) -> &'a [Vec<f32>],
+    F: FnMut(u32, u32, &[f32; 3]) -> (Rgb<u8>, [f32; 3]),
+{
     let width = image_rgb8.width() as i32;
     let height = image_rgb8.height() as i32;
+    let (origine_y, origine_x) = (origine.0 as i32, origine.1 as i32);
 
-    for y in 0..height{
-        for x in 0..width{
-            let pixel = image_rgb8.get_pixel_mut(x as u32, y as u32);
-            let ancien_pixel = *pixel;
-            let nouveau_pixel = couleur_la_plus_proche(&ancien_pixel, &vec![Rgb([0, 0, 0]), Rgb([255, 255, 255])]);
-            *pixel = nouveau_pixel;
+    for y in 0..height {
+        let sens = if serpentin && y % 2 == 1 { -1 } else { 1 };
+        for colonne in 0..width {
+            let x = if sens == 1 { colonne } else { width - 1 - colonne };
+            let ancien_pixel = tampon[(y * width + x) as usize];
+            let (couleur, nouveau_pixel) = quantifier(x as u32, y as u32, &ancien_pixel);
+            image_rgb8.put_pixel(x as u32, y as u32, couleur);
             let erreur = [
-                ancien_pixel[0] as f32 - nouveau_pixel[0] as f32,
-                ancien_pixel[1] as f32 - nouveau_pixel[1] as f32, 
-                ancien_pixel[2] as f32 - nouveau_pixel[2] as f32
+                ancien_pixel[0] - nouveau_pixel[0],
+                ancien_pixel[1] - nouveau_pixel[1],
+                ancien_pixel[2] - nouveau_pixel[2]
             ];
 
-            //pixel de droite
-            if x + 1 < width {
-                let new_pixel = image_rgb8.get_pixel_mut((x + 1) as u32, y as u32);
-                new_pixel[0] = (new_pixel[0] as f32 + erreur[0] * 0.5) as u8;
-                new_pixel[1] = (new_pixel[1] as f32 + erreur[1] * 0.5) as u8;
-                new_pixel[2] = (new_pixel[2] as f32 + erreur[2] * 0.5) as u8;
+            let matrix = noyau(x as u32, y as u32);
+            for (i, ligne) in matrix.iter().enumerate() {
+                for (j, poids) in ligne.iter().enumerate() {
+                    let new_x = x + sens * (j as i32 - origine_x);
+                    let new_y = y + i as i32 - origine_y;
+                    if new_x >= 0 && new_y >= 0 && new_x < width && new_y < height {
+                        let new_pixel = &mut tampon[(new_y * width + new_x) as usize];
+                        new_pixel[0] += erreur[0] * poids;
+                        new_pixel[1] += erreur[1] * poids;
+                        new_pixel[2] += erreur[2] * poids;
+                    }
+                }
             }
+        }
+    }
+}
 
-            //pixel en dessous
-            if y + 1 < height {
-                let new_pixel = image_rgb8.get_pixel_mut(x as u32, (y + 1) as u32);
-                new_pixel[0] = (new_pixel[0] as f32 + erreur[0] * 0.5) as u8;
-                new_pixel[1] = (new_pixel[1] as f32 + erreur[1] * 0.5) as u8;
-                new_pixel[2] = (new_pixel[2] as f32 + erreur[2] * 0.5) as u8;
-            }
+/// Diffusion d'erreur générique sur les composantes sRGB, vers la couleur de la palette la plus
+/// proche selon la métrique donnée
+pub fn diffusion_erreur_generique(image_rgb8: &mut RgbImage, couleurs_palette: &[Rgb<u8>], matrix: &[Vec<f32>], origine: (usize, usize), metrique: MetriqueCouleur, serpentin: bool){
+    let coordonnees_palette: Vec<[f32; 3]> = couleurs_palette.iter().map(|couleur| metrique.coordonnees(couleur)).collect();
+    let tampon: Vec<[f32; 3]> = image_rgb8
+        .pixels()
+        .map(|pixel| pixel.0.map(|c| c as f32))
+        .collect();
+
+    diffuser_erreur(image_rgb8, tampon, origine, serpentin, |_, _| matrix, |_, _, pixel| {
+        quantifier_srgb(pixel, couleurs_palette, &coordonnees_palette, metrique)
+    });
+}
+
+/// Trouver la couleur de la palette la plus proche d'une valeur sRGB accumulée (éventuellement hors
+/// de 0..255), et renvoyer cette couleur avec sa valeur en f32. `coordonnees_palette` contient les
+/// couleurs de la palette déjà converties pour la métrique
+pub(crate) fn quantifier_srgb(
+    pixel: &[f32; 3],
+    couleurs_palette: &[Rgb<u8>],
+    coordonnees_palette: &[[f32; 3]],
+    metrique: MetriqueCouleur,
+) -> (Rgb<u8>, [f32; 3]) {
+    // La valeur accumulée peut sortir de 0..255 : on la borne seulement pour chercher la couleur
+    let pixel_borne = Rgb(pixel.map(|c| c.round().clamp(0.0, 255.0) as u8));
+    let coordonnees_pixel = metrique.coordonnees(&pixel_borne);
+    let mut distance_min = f32::MAX;
+    let mut plus_proche = None;
+    for (i, coordonnees) in coordonnees_palette.iter().enumerate() {
+        let distance = metrique.distance_coordonnees(&coordonnees_pixel, coordonnees);
+        if distance < distance_min {
+            distance_min = distance;
+            plus_proche = Some(i);
         }
     }
+    match plus_proche {
+        Some(indice) => (couleurs_palette[indice], couleurs_palette[indice].0.map(|c| c as f32)),
+        // Sans palette, le pixel est conservé
+        None => (pixel_borne, *pixel),
+    }
 }
 
-pub fn diffusion_erreur_generique(image_rgb8: &mut RgbImage, couleurs_palette: Vec<Rgb<u8>>, matrix: Vec<Vec<f32>>){
-    let width = image_rgb8.width() as i32;
-    let height = image_rgb8.height() as i32;
+/// Coordonnées du point d'indice `d` sur la courbe de Hilbert couvrant un carré de côté `cote`
+/// (puissance de 2), partant de (0, 0) et finissant en (cote - 1, 0)
+fn point_hilbert(cote: u32, d: u64) -> (u32, u32) {
+    let (mut x, mut y) = (0u32, 0u32);
+    let mut t = d;
+    let mut s = 1;
+    while s < cote {
+        let rx = ((t / 2) & 1) as u32;
+        let ry = ((t ^ rx as u64) & 1) as u32;
+        // Rotation du quadrant
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    (x, y)
+}
 
-    let matrix_height = matrix.len() as i32;
-    let matrix_width = matrix[0].len() as i32;
+/// Ordre de parcours d'une image de taille quelconque le long de courbes de Hilbert : l'image est
+/// couverte par des carrés dont le côté est une puissance de 2, mis bout à bout dans sa plus grande
+/// dimension, et les points hors de l'image sont sautés. La fin de chaque courbe touche le début
+/// de la suivante, le parcours reste donc continu
+pub fn parcours_hilbert(largeur: u32, hauteur: u32) -> Vec<(u32, u32)> {
+    if largeur == 0 || hauteur == 0 {
+        return Vec::new();
+    }
+    let cote = largeur.min(hauteur).next_power_of_two();
+    let horizontal = largeur >= hauteur;
+    let blocs = largeur.max(hauteur).div_ceil(cote);
+    let mut parcours = Vec::with_capacity((largeur * hauteur) as usize);
+    for bloc in 0..blocs {
+        for d in 0..(cote as u64 * cote as u64) {
+            let (u, v) = point_hilbert(cote, d);
+            // En hauteur, la courbe est transposée pour finir au bas du carré
+            let (x, y) = if horizontal { (bloc * cote + u, v) } else { (v, bloc * cote + u) };
+            if x < largeur && y < hauteur {
+                parcours.push((x, y));
+            }
+        }
+    }
+    parcours
+}
 
-    for y in 0..height {
-        for x in 0..width {
-            let pixel = image_rgb8.get_pixel_mut(x as u32, y as u32);
-            let ancien_pixel = *pixel;
-            let nouveau_pixel = couleur_la_plus_proche(&ancien_pixel, &couleurs_palette);
-            *pixel = nouveau_pixel;
-            let erreur = [
-                ancien_pixel[0] as f32 - nouveau_pixel[0] as f32,
-                ancien_pixel[1] as f32 - nouveau_pixel[1] as f32, 
-                ancien_pixel[2] as f32 - nouveau_pixel[2] as f32
-            ];
+/// Diffusion d'erreur de Riemersma : les pixels sont parcourus le long d'une courbe de Hilbert et
+/// chacun reçoit la somme des `longueur_historique` dernières erreurs, pondérées de 1 pour la plus
+/// récente à `rapport` pour la plus ancienne selon une décroissance exponentielle
+pub fn diffusion_riemersma(image_rgb8: &mut RgbImage, couleurs_palette: &[Rgb<u8>], metrique: MetriqueCouleur, longueur_historique: usize, rapport: f32) {
+    if couleurs_palette.is_empty() || longueur_historique == 0 {
+        return;
+    }
+    let coordonnees_palette: Vec<[f32; 3]> = couleurs_palette.iter().map(|couleur| metrique.coordonnees(couleur)).collect();
+    let poids: Vec<f32> = (0..longueur_historique)
+        .map(|i| {
+            let age = (longueur_historique - 1 - i) as f32 / (longueur_historique - 1).max(1) as f32;
+            rapport.powf(age)
+        })
+        .collect();
+    // Les erreurs sont rangées de la plus ancienne à la plus récente
+    let mut historique: VecDeque<[f32; 3]> = VecDeque::from(vec![[0.0; 3]; longueur_historique]);
+
+    for (x, y) in parcours_hilbert(image_rgb8.width(), image_rgb8.height()) {
+        let pixel = image_rgb8.get_pixel(x, y).0.map(|c| c as f32);
+        let mut valeur = pixel;
+        for (erreur, p) in historique.iter().zip(&poids) {
+            for (composante, e) in valeur.iter_mut().zip(erreur) {
+                *composante += e * p;
+            }
+        }
+        let (couleur, valeur_couleur) = quantifier_srgb(&valeur, couleurs_palette, &coordonnees_palette, metrique);
+        image_rgb8.put_pixel(x, y, couleur);
+        historique.pop_front();
+        historique.push_back([
+            pixel[0] - valeur_couleur[0],
+            pixel[1] - valeur_couleur[1],
+            pixel[2] - valeur_couleur[2]
+        ]);
+    }
+}
 
-            for i in 0..matrix_height {
-                for j in 0..matrix_width {
-                    let new_x = x + j - matrix_width / 2;
-                    let new_y = y + i - matrix_height / 2;
-                    if new_x >= 0 && new_y >= 0 && new_x < width && new_y < height {
-                        let new_pixel = image_rgb8.get_pixel_mut(new_x as u32, new_y as u32);
-                        new_pixel[0] = (new_pixel[0] as f32 + erreur[0] * matrix[i as usize][j as usize]) as u8;
-                        new_pixel[1] = (new_pixel[1] as f32 + erreur[1] * matrix[i as usize][j as usize]) as u8;
-                        new_pixel[2] = (new_pixel[2] as f32 + erreur[2] * matrix[i as usize][j as usize]) as u8;
-                    }
+/// Diffusion d'erreur générique en lumière linéaire : les pixels sont décodés de la courbe
+/// gamma sRGB et l'erreur est calculée et diffusée sur les intensités physiques. Avec la métrique
+/// euclidienne, la couleur la plus proche est cherchée dans l'espace RGB linéaire ; les autres
+/// métriques comparent la valeur accumulée, réencodée en sRGB, aux couleurs de la palette
+pub fn diffusion_erreur_lineaire(image_rgb8: &mut RgbImage, couleurs_palette: &[Rgb<u8>], matrix: &[Vec<f32>], origine: (usize, usize), metrique: MetriqueCouleur, serpentin: bool){
+    let palette_lineaire: Vec<[f32; 3]> = couleurs_palette
+        .iter()
+        .map(|couleur| couleur.0.map(espaces_couleur::srgb_vers_lineaire))
+        .collect();
+    let coordonnees_palette: Vec<[f32; 3]> = couleurs_palette.iter().map(|couleur| metrique.coordonnees(couleur)).collect();
+    let tampon: Vec<[f32; 3]> = image_rgb8
+        .pixels()
+        .map(|pixel| pixel.0.map(espaces_couleur::srgb_vers_lineaire))
+        .collect();
+
+    diffuser_erreur(image_rgb8, tampon, origine, serpentin, |_, _| matrix, |_, _, pixel| {
+        let mut distance_min = f32::MAX;
+        let mut plus_proche = None;
+        if metrique == MetriqueCouleur::Euclidienne {
+            for (i, couleur) in palette_lineaire.iter().enumerate() {
+                let distance = (pixel[0] - couleur[0]).powi(2)
+                    + (pixel[1] - couleur[1]).powi(2)
+                    + (pixel[2] - couleur[2]).powi(2);
+                if distance < distance_min {
+                    distance_min = distance;
+                    plus_proche = Some(i);
+                }
+            }
+        } else {
+            let coordonnees_pixel = metrique.coordonnees(&Rgb(pixel.map(espaces_couleur::lineaire_vers_srgb)));
+            for (i, coordonnees) in coordonnees_palette.iter().enumerate() {
+                let distance = metrique.distance_coordonnees(&coordonnees_pixel, coordonnees);
+                if distance < distance_min {
+                    distance_min = distance;
+                    plus_proche = Some(i);
                 }
             }
         }
-    } 
-} 
+        match plus_proche {
+            Some(indice) => (couleurs_palette[indice], palette_lineaire[indice]),
+            // Sans palette, le pixel est conservé
+            None => (Rgb(pixel.map(espaces_couleur::lineaire_vers_srgb)), *pixel),
+        }
+    });
+}
 
 pub fn simple_2_d() -> Vec<Vec<f32>> {
     vec![
@@ -328,3 +571,103 @@ pub fn atkinson() -> Vec<Vec<f32>> {
         vec![0.0, 0.0, 1.0 / 8.0, 0.0, 0.0]
     ]
 }
+
+pub fn stucki() -> Vec<Vec<f32>> {
+    vec![
+        vec![0.0, 0.0, 0.0, 0.0, 0.0],
+        vec![0.0, 0.0, 0.0, 0.0, 0.0],
+        vec![0.0, 0.0, 0.0, 8.0 / 42.0, 4.0 / 42.0],
+        vec![2.0 / 42.0, 4.0 / 42.0, 8.0 / 42.0, 4.0 / 42.0, 2.0 / 42.0],
+        vec![1.0 / 42.0, 2.0 / 42.0, 4.0 / 42.0, 2.0 / 42.0, 1.0 / 42.0]
+    ]
+}
+
+pub fn burkes() -> Vec<Vec<f32>> {
+    vec![
+        vec![0.0, 0.0, 0.0, 0.0, 0.0],
+        vec![0.0, 0.0, 0.0, 8.0 / 32.0, 4.0 / 32.0],
+        vec![2.0 / 32.0, 4.0 / 32.0, 8.0 / 32.0, 4.0 / 32.0, 2.0 / 32.0]
+    ]
+}
+
+pub fn sierra_3() -> Vec<Vec<f32>> {
+    vec![
+        vec![0.0, 0.0, 0.0, 0.0, 0.0],
+        vec![0.0, 0.0, 0.0, 0.0, 0.0],
+        vec![0.0, 0.0, 0.0, 5.0 / 32.0, 3.0 / 32.0],
+        vec![2.0 / 32.0, 4.0 / 32.0, 5.0 / 32.0, 4.0 / 32.0, 2.0 / 32.0],
+        vec![0.0, 2.0 / 32.0, 3.0 / 32.0, 2.0 / 32.0, 0.0]
+    ]
+}
+
+pub fn sierra_2() -> Vec<Vec<f32>> {
+    vec![
+        vec![0.0, 0.0, 0.0, 0.0, 0.0],
+        vec![0.0, 0.0, 0.0, 4.0 / 16.0, 3.0 / 16.0],
+        vec![1.0 / 16.0, 2.0 / 16.0, 3.0 / 16.0, 2.0 / 16.0, 1.0 / 16.0]
+    ]
+}
+
+pub fn sierra_lite() -> Vec<Vec<f32>> {
+    vec![
+        vec![0.0, 0.0, 0.0],
+        vec![0.0, 0.0, 2.0 / 4.0],
+        vec![1.0 / 4.0, 1.0 / 4.0, 0.0]
+    ]
+}
+
+/// Noyau de Stevenson et Arce, conçu pour une grille hexagonale : les poids sont répartis
+/// une colonne sur deux et décalés d'une colonne à chaque ligne
+pub fn stevenson_arce() -> Vec<Vec<f32>> {
+    vec![
+        vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+        vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+        vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+        vec![0.0, 0.0, 0.0, 0.0, 0.0, 32.0 / 200.0, 0.0],
+        vec![12.0 / 200.0, 0.0, 26.0 / 200.0, 0.0, 30.0 / 200.0, 0.0, 16.0 / 200.0],
+        vec![0.0, 12.0 / 200.0, 0.0, 26.0 / 200.0, 0.0, 12.0 / 200.0, 0.0],
+        vec![5.0 / 200.0, 0.0, 12.0 / 200.0, 0.0, 12.0 / 200.0, 0.0, 5.0 / 200.0]
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seuil_otsu_separe_un_histogramme_bimodal() {
+        let mut histogramme = [0u64; 256];
+        for (niveau, nombre) in [(40, 10), (50, 30), (60, 10), (190, 20), (200, 60), (210, 20)] {
+            histogramme[niveau] = nombre;
+        }
+        let seuil = seuil_otsu(&histogramme);
+        assert!(seuil > 60.0 && seuil < 190.0, "seuil {}", seuil);
+        assert_eq!(seuil.fract(), 0.5);
+    }
+
+    #[test]
+    fn seuil_otsu_tombe_entre_deux_cases() {
+        let mut image = RgbImage::from_pixel(4, 4, Rgb([50, 50, 50]));
+        for x in 0..4 {
+            for y in 0..2 {
+                image.put_pixel(x, y, Rgb([200, 200, 200]));
+            }
+        }
+        let histogramme = histogramme_luminosite(&image);
+        let seuil = seuil_otsu(&histogramme);
+        assert_eq!(seuil, 50.5);
+        // Les pixels de la case du seuil restent du côté sombre
+        monochrome_par_seuillage(&mut image, seuil, Rgb([255, 255, 255]), Rgb([0, 0, 0]));
+        assert_eq!(*image.get_pixel(0, 3), Rgb([0, 0, 0]));
+        assert_eq!(*image.get_pixel(0, 0), Rgb([255, 255, 255]));
+    }
+
+    #[test]
+    fn seuil_median_borne_haute_de_la_case() {
+        let mut histogramme = [0u64; 256];
+        histogramme[10] = 3;
+        histogramme[100] = 2;
+        assert_eq!(seuil_median(&histogramme), 10.5);
+        assert_eq!(seuil_median(&[0u64; 256]), 128.0);
+    }
+}